@@ -0,0 +1,148 @@
+/// Agents walking found paths
+use crate::board::Terrain;
+use crate::pathfinder::cbs::{Conflict, Plan};
+use nannou::prelude::*;
+use once_cell::sync::Lazy;
+
+static COLOR_AGENT: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0xFF, 0x98, 0x00));
+static COLOR_CONFLICT: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0xFF, 0x00, 0x00));
+/// Colours of a team, agents get them in turn
+static COLOR_TEAM: Lazy<[Rgb8; 8]> = Lazy::new(|| {
+    [
+        Rgb8::new(0xE6, 0x19, 0x4B),
+        Rgb8::new(0x3C, 0xB4, 0x4B),
+        Rgb8::new(0x43, 0x63, 0xD8),
+        Rgb8::new(0xF5, 0x82, 0x31),
+        Rgb8::new(0x91, 0x1E, 0xB4),
+        Rgb8::new(0x42, 0xD4, 0xF4),
+        Rgb8::new(0xF0, 0x32, 0xE6),
+        Rgb8::new(0x80, 0x80, 0x00),
+    ]
+});
+
+pub struct Agent {
+    path: Vec<(usize, usize)>,
+    /// Index of the current cell in the path
+    step: usize,
+}
+
+impl Agent {
+    pub fn new(path: Vec<(usize, usize)>) -> Agent {
+        Agent { path, step: 0 }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        self.path[self.step]
+    }
+
+    /// Cells left to walk, starting with the current one
+    pub fn remaining(&self) -> &[(usize, usize)] {
+        &self.path[self.step..]
+    }
+
+    pub fn has_arrived(&self) -> bool {
+        self.step + 1 >= self.path.len()
+    }
+
+    /// Whether a cell ahead became a wall since the path was found
+    pub fn is_blocked(&self, terrain: &Terrain) -> bool {
+        self.remaining()[1..]
+            .iter()
+            .any(|&(i, j)| !terrain.is_walkable(i, j))
+    }
+
+    /// Moves to the next cell of the path
+    pub fn advance(&mut self) {
+        if !self.has_arrived() {
+            self.step += 1;
+        }
+    }
+
+    /// Follows a new path, which starts where the agent stands
+    pub fn follow(&mut self, path: Vec<(usize, usize)>) {
+        self.path = path;
+        self.step = 0;
+    }
+
+    pub fn draw(&self, draw: &Draw, terrain: &Terrain) {
+        let (i, j) = self.position();
+        let Some(cell) = terrain.get(i, j) else {
+            return;
+        };
+        let size = terrain.cell_size() * 0.6;
+
+        draw.ellipse()
+            .xy(cell.pos)
+            .w_h(size, size)
+            .color(*COLOR_AGENT);
+    }
+}
+
+/// Several agents moving in lockstep along a multi-agent plan
+pub struct Team {
+    plan: Plan,
+    time: usize,
+}
+
+impl Team {
+    pub fn new(plan: Plan) -> Team {
+        Team { plan, time: 0 }
+    }
+
+    /// Moves every agent one tick further, returns false once all arrived
+    pub fn advance(&mut self) -> bool {
+        if self.time >= self.plan.duration() {
+            return false;
+        }
+        self.time += 1;
+        true
+    }
+
+    pub fn draw(&self, draw: &Draw, terrain: &Terrain) {
+        let size = terrain.cell_size();
+        let color = |agent: usize| COLOR_TEAM[agent % COLOR_TEAM.len()];
+
+        for (agent, path) in self.plan.paths.iter().enumerate() {
+            let points = path
+                .iter()
+                .filter_map(|&(i, j)| terrain.get(i, j))
+                .map(|cell| cell.pos);
+            draw.polyline()
+                .weight(size / 8.)
+                .join_round()
+                .color(color(agent))
+                .points(points);
+        }
+
+        // conflicts left by the fallbacks stay outlined
+        for conflict in &self.plan.conflicts {
+            let cells = match *conflict {
+                Conflict::Vertex { pos, .. } => vec![pos],
+                Conflict::Edge { from, to, .. } => vec![from, to],
+            };
+
+            for cell in cells.iter().filter_map(|&(i, j)| terrain.get(i, j)) {
+                draw.rect()
+                    .xy(cell.pos)
+                    .w_h(size, size)
+                    .no_fill()
+                    .stroke_weight(size / 6.)
+                    .stroke(*COLOR_CONFLICT);
+            }
+        }
+
+        for agent in 0..self.plan.paths.len() {
+            let (i, j) = self.plan.position(agent, self.time);
+            let Some(cell) = terrain.get(i, j) else {
+                continue;
+            };
+
+            draw.ellipse()
+                .xy(cell.pos)
+                .w_h(size * 0.6, size * 0.6)
+                .stroke_weight(size / 20.)
+                .stroke(BLACK)
+                .color(color(agent));
+        }
+    }
+}
@@ -0,0 +1,264 @@
+/// Headless benchmark over MovingAI scenarios, prints one CSV line per query
+/// (https://movingai.com/benchmarks/formats.html)
+use crate::board::{CellType, Connectivity, Terrain};
+use crate::pathfinder::{AStarSettings, Algorithm, Heuristic, StepResult};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+const USAGE: &str = "Usage: pathfinding bench <file.map> <file.scen> \
+[--algorithms bfs,dfs,dijkstra,astar,bibfs,biastar,jps,jpsplus,dstar] [--connectivity 4|8] \
+[--heuristic manhattan|euclidean|chebyshev|octile] [--weight W]";
+
+/// One query of a scenario file, already in board coordinates
+pub struct Scenario {
+    pub bucket: usize,
+    pub start: (usize, usize),
+    pub goal: (usize, usize),
+    pub optimal: f32,
+}
+
+/// Result of a single query run to completion
+pub struct Measure {
+    pub expanded: usize,
+    pub length: Option<f32>,
+    pub micros: u128,
+}
+
+/// Entry point of `pathfinding bench ...`, args are the ones after `bench`
+pub fn run(args: &[String]) -> Result<(), String> {
+    let [map, scen, options @ ..] = args else {
+        return Err(USAGE.to_string());
+    };
+
+    let mut algorithms = vec![
+        Algorithm::BreadthFirst,
+        Algorithm::DepthFirst,
+        Algorithm::Dijkstra,
+        Algorithm::AStar,
+        Algorithm::BidirectionalBfs,
+        Algorithm::BidirectionalAStar,
+        Algorithm::JumpPoint,
+        Algorithm::JumpPointPlus,
+        Algorithm::DStarLite,
+    ];
+    let mut connectivity = Connectivity::Eight;
+    let mut settings = AStarSettings {
+        heuristic: Heuristic::Octile,
+        ..Default::default()
+    };
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| format!("Missing value after {}", option))?;
+
+        match option.as_str() {
+            "--algorithms" => {
+                algorithms = value
+                    .split(',')
+                    .map(|name| {
+                        parse_algorithm(name).ok_or_else(|| {
+                            format!("Unknown algorithm {}", name)
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "--connectivity" => {
+                connectivity = match value.as_str() {
+                    "4" => Connectivity::Four,
+                    "8" => Connectivity::Eight,
+                    _ => return Err(format!("Unknown connectivity {}", value)),
+                };
+            }
+            "--heuristic" => {
+                settings.heuristic = parse_heuristic(value)
+                    .ok_or_else(|| format!("Unknown heuristic {}", value))?;
+            }
+            "--weight" => {
+                settings.weight = value
+                    .parse()
+                    .map_err(|_| format!("Invalid weight {}", value))?;
+            }
+            _ => return Err(format!("Unknown option {}\n{}", option, USAGE)),
+        }
+    }
+
+    let mut terrain = Terrain::load_map(Path::new(map))
+        .map_err(|e| format!("Could not load {}: {}", map, e))?;
+    terrain.connectivity = connectivity;
+    // the optimal lengths count every move the same, swamps and water
+    // included
+    for i in 0..terrain.width() {
+        for j in 0..terrain.height() {
+            if terrain.is_walkable(i, j) {
+                terrain.set(i, j, CellType::Empty);
+            }
+        }
+    }
+
+    let text = fs::read_to_string(scen)
+        .map_err(|e| format!("Could not read {}: {}", scen, e))?;
+    let scenarios = parse_scenarios(&text, &terrain)?;
+
+    println!(
+        "algorithm,bucket,start_x,start_y,goal_x,goal_y,\
+expanded,length,optimal,deviation,time_us"
+    );
+
+    for scenario in &scenarios {
+        for algorithm in &algorithms {
+            let measure =
+                measure(&mut terrain, *algorithm, &settings, scenario);
+
+            // back to the file coordinates, y going down
+            let flip = |(x, y): (usize, usize)| (x, terrain.height() - 1 - y);
+            let (start_x, start_y) = flip(scenario.start);
+            let (goal_x, goal_y) = flip(scenario.goal);
+
+            let (length, deviation) = match measure.length {
+                Some(length) => (
+                    length.to_string(),
+                    (length - scenario.optimal).to_string(),
+                ),
+                None => (String::new(), String::new()),
+            };
+
+            println!(
+                "{:?},{},{},{},{},{},{},{},{},{},{}",
+                algorithm,
+                scenario.bucket,
+                start_x,
+                start_y,
+                goal_x,
+                goal_y,
+                measure.expanded,
+                length,
+                scenario.optimal,
+                deviation,
+                measure.micros
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs one query with one algorithm, without drawing anything
+pub fn measure(
+    terrain: &mut Terrain,
+    algorithm: Algorithm,
+    settings: &AStarSettings,
+    scenario: &Scenario,
+) -> Measure {
+    terrain.reset_search();
+    let mut pathfinder = algorithm.build(settings);
+    // like loading the map, what only depends on it isn't timed
+    pathfinder.prepare(terrain);
+
+    let begin = Instant::now();
+    pathfinder.init(terrain, scenario.start, &[scenario.goal]);
+
+    let result = loop {
+        match pathfinder.step(terrain) {
+            StepResult::Running => (),
+            result => break result,
+        }
+    };
+    let micros = begin.elapsed().as_micros();
+
+    // steps expand more than one node in JPS or bidirectional searches
+    let expanded = terrain.expanded();
+
+    let length = match result {
+        StepResult::Found => {
+            pathfinder.path().map(|path| terrain.path_cost(&path))
+        }
+        _ => None,
+    };
+
+    Measure {
+        expanded,
+        length,
+        micros,
+    }
+}
+
+/// Reads a .scen file, flipping y so that it matches the board, every start
+/// and goal has to be a walkable cell of the board
+pub fn parse_scenarios(
+    text: &str,
+    terrain: &Terrain,
+) -> Result<Vec<Scenario>, String> {
+    let (w, h) = (terrain.width(), terrain.height());
+    let mut scenarios = Vec::new();
+
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        if line.starts_with("version") {
+            continue;
+        }
+
+        // bucket, map, map width, map height, start x, start y,
+        // goal x, goal y, optimal length
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 9 {
+            return Err(format!("Invalid scenario line `{}`", line));
+        }
+
+        let number = |index: usize| -> Result<usize, String> {
+            fields[index]
+                .parse()
+                .map_err(|_| format!("Invalid number in `{}`", line))
+        };
+        let (start_x, start_y) = (number(4)?, number(5)?);
+        let (goal_x, goal_y) = (number(6)?, number(7)?);
+        if start_x >= w || goal_x >= w || start_y >= h || goal_y >= h {
+            return Err(format!("Scenario out of the map `{}`", line));
+        }
+
+        let (start, goal) =
+            ((start_x, h - 1 - start_y), (goal_x, h - 1 - goal_y));
+        if !terrain.is_walkable(start.0, start.1)
+            || !terrain.is_walkable(goal.0, goal.1)
+        {
+            return Err(format!("Scenario on a wall `{}`", line));
+        }
+
+        scenarios.push(Scenario {
+            bucket: number(0)?,
+            start,
+            goal,
+            optimal: fields[8]
+                .parse()
+                .map_err(|_| format!("Invalid length in `{}`", line))?,
+        });
+    }
+
+    Ok(scenarios)
+}
+
+fn parse_algorithm(name: &str) -> Option<Algorithm> {
+    match name {
+        "bfs" => Some(Algorithm::BreadthFirst),
+        "dfs" => Some(Algorithm::DepthFirst),
+        "dijkstra" => Some(Algorithm::Dijkstra),
+        "astar" => Some(Algorithm::AStar),
+        "bibfs" => Some(Algorithm::BidirectionalBfs),
+        "biastar" => Some(Algorithm::BidirectionalAStar),
+        "jps" => Some(Algorithm::JumpPoint),
+        "jpsplus" => Some(Algorithm::JumpPointPlus),
+        "dstar" => Some(Algorithm::DStarLite),
+        _ => None,
+    }
+}
+
+fn parse_heuristic(name: &str) -> Option<Heuristic> {
+    match name {
+        "manhattan" => Some(Heuristic::Manhattan),
+        "euclidean" => Some(Heuristic::Euclidean),
+        "chebyshev" => Some(Heuristic::Chebyshev),
+        "octile" => Some(Heuristic::Octile),
+        _ => None,
+    }
+}
@@ -1,36 +1,204 @@
 /// Board management
+use crate::camera::Camera;
+use crate::heatmap::Heatmap;
+use nannou::image::{DynamicImage, Rgba, RgbaImage};
 use nannou::prelude::*;
 use once_cell::sync::Lazy;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::f32::consts::SQRT_2;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 const CELL_SIDE: f32 = 100.;
 
+/// Source of the map versions, two maps never get the same one
+static MAP_VERSIONS: AtomicU64 = AtomicU64::new(0);
+
 static COLOR_BACKGROUND: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0x80, 0x80, 0x80));
 static COLOR_EMPTY: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0xFF, 0xFF, 0xFF));
 static COLOR_WALL: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0x30, 0x30, 0x30));
 static COLOR_START: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0x22, 0x66, 0x00));
 static COLOR_STOP: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0xFF, 0x4D, 0x4D));
+static COLOR_GRASS: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0x9C, 0xE0, 0x6C));
+static COLOR_SAND: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0xF2, 0xD9, 0x8C));
+static COLOR_WATER: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0x6C, 0xB4, 0xEE));
+static COLOR_MUD: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0xB0, 0x80, 0x50));
+static COLOR_PATH: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0x1E, 0x90, 0xFF));
+static COLOR_FRONTIER: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0xFF, 0xC1, 0x07, 0xA0));
+static COLOR_CLOSED: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0x64, 0x95, 0xED, 0x80));
+static COLOR_FRONTIER_BACK: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0xE0, 0x40, 0xFB, 0xA0));
+static COLOR_CLOSED_BACK: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0xBA, 0x68, 0xC8, 0x80));
+static COLOR_JUMP_POINT: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0xFF, 0x57, 0x22, 0xD0));
+static COLOR_SCANNED: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0xFF, 0xEB, 0x3B, 0x50));
+static COLOR_UPDATED: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0x00, 0xBC, 0xD4, 0xA0));
+static COLOR_ON_PATH: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0x1E, 0x90, 0xFF, 0xC0));
+
+/// Highest cost a cell can have, used to scale the shading
+const MAX_COST: f32 = 8.;
 
 /// A cell could contain all kind of attributes
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum CellType {
     Empty,
     Wall,
     Start,
     Stop,
+    Grass,
+    Sand,
+    Water,
+    Mud,
+}
+
+/// Which cells are considered next to each other
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+/// When a diagonal move may go past the corner of a wall
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CornerCutting {
+    /// Even squeezing between two walls
+    Always,
+    /// Only if at most one of the two sides is a wall
+    OneSideOpen,
+    /// Never past a wall, both sides have to be open
+    BothSidesOpen,
+}
+
+/// State of a cell in the current search, drawn over its type
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SearchState {
+    Unvisited,
+    Frontier,
+    Closed,
+    /// Frontier and closed cells of a search going backward from the goal
+    FrontierBack,
+    ClosedBack,
+    /// Cells a jump point search stopped on, and the ones it went past
+    JumpPoint,
+    Scanned,
+    /// Cells whose cost changed while a search repaired its path
+    Updated,
+    OnPath,
+}
+
+/// What a search knew of a cell when it expanded it
+#[derive(Copy, Clone, Debug)]
+pub struct Expansion {
+    /// Cells expanded before this one
+    pub order: usize,
+    /// Cost from where the search started
+    pub g: f32,
+    /// Cost plus the heuristic estimate, what the cell was picked by
+    pub f: f32,
+}
+
+/// Search layer of a single cell
+#[derive(Copy, Clone)]
+struct SearchMark {
+    state: SearchState,
+    expansion: Option<Expansion>,
+}
+
+/// What a search step changed in the search layer, so that it can be
+/// undone and done again
+pub struct SearchChanges {
+    /// Index of each changed cell, with its marks before and after
+    cells: Vec<(usize, SearchMark, SearchMark)>,
+    /// Count of expanded cells before and after
+    expanded: (usize, usize),
+}
+
+/// Starts and stops of the map, in order since the nth start goes to the
+/// nth stop
+#[derive(Clone, PartialEq)]
+struct Endpoints {
+    starts: Vec<(usize, usize)>,
+    stops: Vec<(usize, usize)>,
+}
+
+/// Cell types and endpoints of the map, to find out what an edit changed
+pub struct MapSnapshot {
+    types: Vec<CellType>,
+    endpoints: Endpoints,
 }
 
+/// What an edit changed on the map, so that it can be undone and done again
+pub struct MapChanges {
+    /// Index of each changed cell, with its type before and after
+    cells: Vec<(usize, CellType, CellType)>,
+    /// Endpoints before and after, kept whole since a new start may have
+    /// erased the old one
+    endpoints: (Endpoints, Endpoints),
+}
+
+/// Texture of the board as last drawn, with what it was made from
+struct Drawn {
+    version: u64,
+    heatmap: Heatmap,
+    /// Values the heatmap colours go between
+    range: Option<(f32, f32)>,
+    texture: wgpu::Texture,
+}
+
+/// The board texture, built again only once the terrain changed
+#[derive(Default)]
+struct TextureCache(RefCell<Option<Drawn>>);
+
+/// A copy of the terrain gets drawn on its own
+impl Clone for TextureCache {
+    fn clone(&self) -> TextureCache {
+        TextureCache::default()
+    }
+}
+
+#[derive(Clone)]
 pub struct Cell {
     pub ctype: CellType,
-    pos: Vec2,
+    pub pos: Vec2,
 }
 
 impl CellType {
+    /// Weighted terrains get darker as they get more expensive
     pub fn color(&self) -> Rgb8 {
+        let base = match self {
+            CellType::Empty => return *COLOR_EMPTY,
+            CellType::Wall => return *COLOR_WALL,
+            CellType::Start => return *COLOR_START,
+            CellType::Stop => return *COLOR_STOP,
+            CellType::Grass => *COLOR_GRASS,
+            CellType::Sand => *COLOR_SAND,
+            CellType::Water => *COLOR_WATER,
+            CellType::Mud => *COLOR_MUD,
+        };
+
+        let shade = 1. - 0.5 * (self.cost().unwrap() - 1.) / (MAX_COST - 1.);
+        Rgb8::new(
+            (base.red as f32 * shade) as u8,
+            (base.green as f32 * shade) as u8,
+            (base.blue as f32 * shade) as u8,
+        )
+    }
+
+    /// Cost of moving into a cell of this type, walls cannot be crossed
+    pub fn cost(&self) -> Option<f32> {
         match self {
-            CellType::Empty => COLOR_EMPTY.clone(),
-            CellType::Wall => COLOR_WALL.clone(),
-            CellType::Start => COLOR_START.clone(),
-            CellType::Stop => COLOR_STOP.clone(),
+            CellType::Wall => None,
+            CellType::Empty | CellType::Start | CellType::Stop => Some(1.),
+            CellType::Grass => Some(2.),
+            CellType::Sand => Some(3.),
+            CellType::Water => Some(5.),
+            CellType::Mud => Some(MAX_COST),
         }
     }
 
@@ -39,20 +207,75 @@ impl CellType {
             CellType::Empty => CellType::Wall,
             CellType::Wall => CellType::Start,
             CellType::Start => CellType::Stop,
-            CellType::Stop => CellType::Empty,
+            CellType::Stop => CellType::Grass,
+            CellType::Grass => CellType::Sand,
+            CellType::Sand => CellType::Water,
+            CellType::Water => CellType::Mud,
+            CellType::Mud => CellType::Empty,
         }
     }
 
     pub fn previous(&self) -> CellType {
         match self {
-            CellType::Empty => CellType::Stop,
+            CellType::Empty => CellType::Mud,
             CellType::Wall => CellType::Empty,
             CellType::Start => CellType::Wall,
             CellType::Stop => CellType::Start,
+            CellType::Grass => CellType::Stop,
+            CellType::Sand => CellType::Grass,
+            CellType::Water => CellType::Sand,
+            CellType::Mud => CellType::Water,
         }
     }
 }
 
+impl Connectivity {
+    pub fn next(&self) -> Connectivity {
+        match self {
+            Connectivity::Four => Connectivity::Eight,
+            Connectivity::Eight => Connectivity::Four,
+        }
+    }
+}
+
+impl CornerCutting {
+    pub fn next(&self) -> CornerCutting {
+        match self {
+            CornerCutting::Always => CornerCutting::OneSideOpen,
+            CornerCutting::OneSideOpen => CornerCutting::BothSidesOpen,
+            CornerCutting::BothSidesOpen => CornerCutting::Always,
+        }
+    }
+}
+
+impl SearchState {
+    pub fn color(&self) -> Option<Rgba8> {
+        match self {
+            SearchState::Unvisited => None,
+            SearchState::Frontier => Some(*COLOR_FRONTIER),
+            SearchState::Closed => Some(*COLOR_CLOSED),
+            SearchState::FrontierBack => Some(*COLOR_FRONTIER_BACK),
+            SearchState::ClosedBack => Some(*COLOR_CLOSED_BACK),
+            SearchState::JumpPoint => Some(*COLOR_JUMP_POINT),
+            SearchState::Scanned => Some(*COLOR_SCANNED),
+            SearchState::Updated => Some(*COLOR_UPDATED),
+            SearchState::OnPath => Some(*COLOR_ON_PATH),
+        }
+    }
+}
+
+/// Colour seen through a translucent one
+fn blend(below: Rgb8, above: Rgba8) -> Rgb8 {
+    let alpha = above.alpha as f32 / 255.;
+    let mix = |b: u8, a: u8| (b as f32 * (1. - alpha) + a as f32 * alpha) as u8;
+
+    Rgb8::new(
+        mix(below.red, above.red),
+        mix(below.green, above.green),
+        mix(below.blue, above.blue),
+    )
+}
+
 impl Cell {
     pub fn color(&self) -> Rgb8 {
         self.ctype.color()
@@ -68,14 +291,36 @@ impl Cell {
 }
 
 /// Structure simulating the terrain (with a board)
+#[derive(Clone)]
 pub struct Terrain {
     cell_size: f32,
     board: Vec<Cell>,
+    search: Vec<SearchState>,
+    expansions: Vec<Option<Expansion>>,
+    expanded: usize,
+    /// Last cell expanded by the search, rewinding leaves it be
+    latest: Option<Expansion>,
+    /// Marks of the cells changed since recording started, as they were
+    recording: Option<Vec<(usize, SearchMark)>>,
+    recorded_expanded: usize,
+    /// Bumped by every change to the cells or the search layer
+    version: u64,
+    /// Changes with the cell types only, the search layer leaves it be
+    map_version: u64,
+    texture: TextureCache,
     w: usize,
     h: usize,
     x_offset: f32,
     y_offset: f32,
-    pub start_stop: [Option<(usize, usize)>; 2],
+    /// Starts and stops in the order they were placed, the nth start and
+    /// the nth stop belong to the same agent
+    pub starts: Vec<(usize, usize)>,
+    pub stops: Vec<(usize, usize)>,
+    /// Placing a start removes the previous one, unless planning for
+    /// several agents
+    pub single_start: bool,
+    pub connectivity: Connectivity,
+    pub corner_cutting: CornerCutting,
 }
 
 impl Terrain {
@@ -85,46 +330,315 @@ impl Terrain {
         let w = (w / CELL_SIDE).ceil() as usize;
         let h = (h / CELL_SIDE).ceil() as usize;
 
-        let mut board: Vec<Cell> = Vec::with_capacity(h * w);
-
-        let x_offset = -((w as f32 - 1.) * CELL_SIDE / 2.);
-        let y_offset = -((h as f32 - 1.) * CELL_SIDE / 2.);
+        println!("Board has {} tiles ({}x{})", w * h, w, h);
 
-        for i in 0..w {
-            for j in 0..h {
-                let x = x_offset + i as f32 * CELL_SIDE;
-                let y = y_offset + j as f32 * CELL_SIDE;
+        Terrain::with_size(w, h, CELL_SIDE)
+    }
 
-                let new_cell = Cell {
-                    ctype: CellType::Empty,
-                    pos: Vec2::new(x, y),
-                };
+    /// Makes an empty terrain of w x h cells
+    pub fn with_size(w: usize, h: usize, cell_size: f32) -> Terrain {
+        let mut board: Vec<Cell> = Vec::with_capacity(h * w);
 
-                board.push(new_cell);
-            }
+        for _ in 0..w * h {
+            board.push(Cell {
+                ctype: CellType::Empty,
+                pos: Vec2::ZERO,
+            });
         }
 
-        println!("Board has {} tiles ({}x{})", w * h, w, h);
-
-        Terrain {
-            cell_size: CELL_SIDE,
+        let mut terrain = Terrain {
+            cell_size,
             board,
+            search: vec![SearchState::Unvisited; w * h],
+            expansions: vec![None; w * h],
+            expanded: 0,
+            latest: None,
+            recording: None,
+            recorded_expanded: 0,
+            version: 0,
+            map_version: MAP_VERSIONS.fetch_add(1, Ordering::Relaxed),
+            texture: TextureCache::default(),
             w,
             h,
-            x_offset,
-            y_offset,
-            start_stop: [None; 2],
+            x_offset: 0.,
+            y_offset: 0.,
+            starts: Vec::new(),
+            stops: Vec::new(),
+            single_start: true,
+            connectivity: Connectivity::Four,
+            corner_cutting: CornerCutting::BothSidesOpen,
+        };
+        terrain.layout();
+
+        terrain
+    }
+
+    /// Resizes the cells so that the whole board fits in w x h pixels,
+    /// they never get bigger than CELL_SIDE
+    pub fn fit(&mut self, w: f32, h: f32) {
+        self.cell_size =
+            (w / self.w as f32).min(h / self.h as f32).min(CELL_SIDE);
+        self.layout();
+    }
+
+    /// Places every cell around the origin, knowing the cell size
+    fn layout(&mut self) {
+        self.x_offset = -((self.w as f32 - 1.) * self.cell_size / 2.);
+        self.y_offset = -((self.h as f32 - 1.) * self.cell_size / 2.);
+
+        for i in 0..self.w {
+            for j in 0..self.h {
+                let x = self.x_offset + i as f32 * self.cell_size;
+                let y = self.y_offset + j as f32 * self.cell_size;
+
+                self.board[i * self.h + j].pos = Vec2::new(x, y);
+            }
         }
     }
 
+    pub fn width(&self) -> usize {
+        self.w
+    }
+
+    pub fn height(&self) -> usize {
+        self.h
+    }
+
+    /// Start of a single agent search, the last one placed
+    pub fn start(&self) -> Option<(usize, usize)> {
+        self.starts.last().copied()
+    }
+
+    /// Tells whether the cell types changed since it was read, whatever
+    /// the searches did meanwhile
+    pub fn map_version(&self) -> u64 {
+        self.map_version
+    }
+
+    fn touch_map(&mut self) {
+        self.version += 1;
+        self.map_version = MAP_VERSIONS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
     pub fn get(&self, i: usize, j: usize) -> Option<&Cell> {
         (i < self.w && j < self.h).then(|| &self.board[i * self.h + j])
     }
 
     pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut Cell> {
+        self.touch_map();
         (i < self.w && j < self.h).then(|| &mut self.board[i * self.h + j])
     }
 
+    /// Whether a search can go through this cell
+    pub fn is_walkable(&self, i: usize, j: usize) -> bool {
+        self.cost(i, j).is_some()
+    }
+
+    /// Cost of moving into this cell, None if it cannot be entered
+    pub fn cost(&self, i: usize, j: usize) -> Option<f32> {
+        self.get(i, j).and_then(|cell| cell.ctype.cost())
+    }
+
+    /// Cost of moving between two neighbours, diagonals are sqrt(2) longer
+    pub fn move_cost(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Option<f32> {
+        let cost = self.cost(to.0, to.1)?;
+
+        if from.0 != to.0 && from.1 != to.1 {
+            Some(cost * SQRT_2)
+        } else {
+            Some(cost)
+        }
+    }
+
+    /// Sum of the costs of every move along the path
+    pub fn path_cost(&self, path: &[(usize, usize)]) -> f32 {
+        // summing nothing gives -0, not 0
+        path.windows(2)
+            .filter_map(|pair| self.move_cost(pair[0], pair[1]))
+            .fold(0., |cost, move_cost| cost + move_cost)
+    }
+
+    pub fn search_state(&self, i: usize, j: usize) -> Option<SearchState> {
+        (i < self.w && j < self.h).then(|| self.search[i * self.h + j])
+    }
+
+    pub fn set_search_state(&mut self, i: usize, j: usize, state: SearchState) {
+        if i < self.w && j < self.h {
+            self.note(i * self.h + j);
+            self.search[i * self.h + j] = state;
+            self.version += 1;
+        }
+    }
+
+    /// Records that the search expanded a cell, after the previous ones
+    pub fn expand(&mut self, i: usize, j: usize, g: f32, f: f32) {
+        if i < self.w && j < self.h {
+            self.note(i * self.h + j);
+            let expansion = Expansion {
+                order: self.expanded,
+                g,
+                f,
+            };
+            self.expansions[i * self.h + j] = Some(expansion);
+            self.latest = Some(expansion);
+            self.expanded += 1;
+            self.version += 1;
+        }
+    }
+
+    pub fn expansion(&self, i: usize, j: usize) -> Option<Expansion> {
+        (i < self.w && j < self.h)
+            .then(|| self.expansions[i * self.h + j])
+            .flatten()
+    }
+
+    /// Number of cells expanded by the search
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    pub fn latest_expansion(&self) -> Option<Expansion> {
+        self.latest
+    }
+
+    fn mark(&self, index: usize) -> SearchMark {
+        SearchMark {
+            state: self.search[index],
+            expansion: self.expansions[index],
+        }
+    }
+
+    fn set_mark(&mut self, index: usize, mark: SearchMark) {
+        self.search[index] = mark.state;
+        self.expansions[index] = mark.expansion;
+        self.version += 1;
+    }
+
+    /// Keeps the marks of a cell about to change, while recording
+    fn note(&mut self, index: usize) {
+        let mark = self.mark(index);
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push((index, mark));
+        }
+    }
+
+    /// Starts keeping track of the changes made to the search layer
+    pub fn record(&mut self) {
+        self.recording = Some(Vec::new());
+        self.recorded_expanded = self.expanded;
+    }
+
+    /// Changes made since recording started, which then stops
+    pub fn take_changes(&mut self) -> SearchChanges {
+        let mut seen = HashSet::new();
+        let cells = self
+            .recording
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            // a cell changed twice was as first noted
+            .filter(|&(index, _)| seen.insert(index))
+            .map(|(index, before)| (index, before, self.mark(index)))
+            .collect();
+
+        SearchChanges {
+            cells,
+            expanded: (self.recorded_expanded, self.expanded),
+        }
+    }
+
+    /// Puts the search layer back as it was before the changes
+    pub fn undo_changes(&mut self, changes: &SearchChanges) {
+        for &(index, before, _) in &changes.cells {
+            self.set_mark(index, before);
+        }
+        self.expanded = changes.expanded.0;
+    }
+
+    /// Applies the changes again after they were undone
+    pub fn redo_changes(&mut self, changes: &SearchChanges) {
+        for &(index, _, after) in &changes.cells {
+            self.set_mark(index, after);
+        }
+        self.expanded = changes.expanded.1;
+    }
+
+    fn endpoints(&self) -> Endpoints {
+        Endpoints {
+            starts: self.starts.clone(),
+            stops: self.stops.clone(),
+        }
+    }
+
+    fn set_endpoints(&mut self, endpoints: &Endpoints) {
+        self.starts = endpoints.starts.clone();
+        self.stops = endpoints.stops.clone();
+    }
+
+    pub fn snapshot(&self) -> MapSnapshot {
+        MapSnapshot {
+            types: self.board.iter().map(|cell| cell.ctype).collect(),
+            endpoints: self.endpoints(),
+        }
+    }
+
+    /// What changed on the map since the snapshot, None if nothing did
+    pub fn changes_since(&self, snapshot: &MapSnapshot) -> Option<MapChanges> {
+        if snapshot.types.len() != self.board.len() {
+            return None;
+        }
+        let cells: Vec<(usize, CellType, CellType)> = snapshot
+            .types
+            .iter()
+            .zip(&self.board)
+            .enumerate()
+            .filter(|(_, (&before, cell))| before != cell.ctype)
+            .map(|(index, (&before, cell))| (index, before, cell.ctype))
+            .collect();
+
+        let endpoints = self.endpoints();
+        let unchanged = cells.is_empty() && snapshot.endpoints == endpoints;
+        (!unchanged).then(|| MapChanges {
+            cells,
+            endpoints: (snapshot.endpoints.clone(), endpoints),
+        })
+    }
+
+    /// Puts the map back as it was before the changes
+    pub fn undo_map_changes(&mut self, changes: &MapChanges) {
+        for &(index, before, _) in &changes.cells {
+            self.board[index].ctype = before;
+        }
+        self.set_endpoints(&changes.endpoints.0);
+        self.touch_map();
+    }
+
+    /// Applies the changes to the map again after they were undone
+    pub fn redo_map_changes(&mut self, changes: &MapChanges) {
+        for &(index, _, after) in &changes.cells {
+            self.board[index].ctype = after;
+        }
+        self.set_endpoints(&changes.endpoints.1);
+        self.touch_map();
+    }
+
+    /// Forgets everything about the last search, the board stays untouched
+    pub fn reset_search(&mut self) {
+        self.search.fill(SearchState::Unvisited);
+        self.expansions.fill(None);
+        self.expanded = 0;
+        self.latest = None;
+        self.version += 1;
+    }
+
     pub fn get_neighbours(&self, i: usize, j: usize) -> Vec<(usize, usize)> {
         let mut res: Vec<(usize, usize)> = Vec::new();
 
@@ -141,49 +655,272 @@ impl Terrain {
             res.push((i, j + 1));
         }
 
+        if self.connectivity == Connectivity::Four {
+            return res;
+        }
+
+        for (di, dj) in [(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+            let (Some(k), Some(l)) =
+                (i.checked_add_signed(di), j.checked_add_signed(dj))
+            else {
+                continue;
+            };
+            if k >= self.w || l >= self.h {
+                continue;
+            }
+
+            // the two cells the diagonal goes between
+            let open_sides = [(k, j), (i, l)]
+                .iter()
+                .filter(|&&(x, y)| self.is_walkable(x, y))
+                .count();
+
+            let allowed = match self.corner_cutting {
+                CornerCutting::Always => true,
+                CornerCutting::OneSideOpen => open_sides >= 1,
+                CornerCutting::BothSidesOpen => open_sides == 2,
+            };
+            if allowed {
+                res.push((k, l));
+            }
+        }
+
         res
     }
 
-    /// Draws the terrain with the color statics
-    pub fn draw(&self, draw: &Draw) {
-        let rect = Rect::from_w_h(CELL_SIDE, CELL_SIDE);
+    /// Colour of a cell, its search state blended over its type
+    pub fn cell_color(&self, i: usize, j: usize) -> Rgb8 {
+        let cell = &self.board[i * self.h + j];
+        let color = cell.color();
 
-        for i in 0..self.w {
-            for j in 0..self.h {
-                draw.rect()
-                    .xy(self.get(i, j).unwrap().pos)
-                    .wh(rect.wh())
-                    .stroke_weight(1.)
-                    .stroke(COLOR_BACKGROUND.clone())
-                    .color(self.get(i, j).unwrap().color());
-            }
+        // keep start and stop visible whatever the search did
+        if let CellType::Start | CellType::Stop = cell.ctype {
+            return color;
+        }
+
+        match self.search[i * self.h + j].color() {
+            Some(above) => blend(color, above),
+            None => color,
+        }
+    }
+
+    /// Image of one pixel per cell, with the color statics and the search
+    /// state on top of them, unless the heatmap gives another colour
+    fn image(&self, heatmap: Heatmap, range: Option<(f32, f32)>) -> RgbaImage {
+        let overlay = heatmap.colors(self, range);
+
+        RgbaImage::from_fn(self.w as u32, self.h as u32, |x, y| {
+            // rows of an image go down, the board goes up
+            let (i, j) = (x as usize, self.h - 1 - y as usize);
+            let color = overlay(i, j).unwrap_or_else(|| self.cell_color(i, j));
+            Rgba([color.red, color.green, color.blue, 0xFF])
+        })
+    }
+
+    /// Draws the terrain as a single texture, made again only when the
+    /// terrain or the heatmap changed since the last frame, returns the
+    /// range of the heatmap for its legend
+    pub fn draw(
+        &self,
+        app: &App,
+        draw: &Draw,
+        heatmap: Heatmap,
+    ) -> Option<(f32, f32)> {
+        let mut drawn = self.texture.0.borrow_mut();
+        let outdated = drawn.as_ref().is_none_or(|drawn| {
+            drawn.version != self.version || drawn.heatmap != heatmap
+        });
+        if outdated {
+            let range = heatmap.range(self);
+            let image = DynamicImage::ImageRgba8(self.image(heatmap, range));
+            *drawn = Some(Drawn {
+                version: self.version,
+                heatmap,
+                range,
+                texture: wgpu::Texture::from_image(app, &image),
+            });
+        }
+        let drawn = drawn.as_ref().unwrap();
+        let texture = &drawn.texture;
+
+        // cells stay sharp squares however close the camera gets
+        let sampler = wgpu::SamplerBuilder::new()
+            .mag_filter(wgpu::FilterMode::Nearest)
+            .min_filter(wgpu::FilterMode::Nearest)
+            .into_descriptor();
+        draw.sampler(sampler).texture(texture).w_h(
+            self.w as f32 * self.cell_size,
+            self.h as f32 * self.cell_size,
+        );
+
+        drawn.range
+    }
+
+    /// Draws the lines between the cells within an area of the board
+    pub fn draw_grid(&self, draw: &Draw, area: Rect, weight: f32) {
+        let size = self.cell_size;
+        let board = Rect::from_w_h(self.w as f32 * size, self.h as f32 * size);
+        let Some(area) = area.overlap(board) else {
+            return;
+        };
+
+        // first and last lines within the area, along each axis
+        let lines = |low: f32, high: f32, start: f32| {
+            let first = ((low - start) / size).ceil() as usize;
+            let last = ((high - start) / size).floor() as usize;
+            (first..=last).map(move |n| start + n as f32 * size)
+        };
+
+        for x in lines(area.left(), area.right(), board.left()) {
+            draw.line()
+                .start(pt2(x, area.bottom()))
+                .end(pt2(x, area.top()))
+                .weight(weight)
+                .color(*COLOR_BACKGROUND);
+        }
+        for y in lines(area.bottom(), area.top(), board.bottom()) {
+            draw.line()
+                .start(pt2(area.left(), y))
+                .end(pt2(area.right(), y))
+                .weight(weight)
+                .color(*COLOR_BACKGROUND);
+        }
+    }
+
+    /// Numbers the stops of a route in visiting order
+    pub fn draw_waypoints(&self, draw: &Draw, stops: &[(usize, usize)]) {
+        let font_size = (self.cell_size * 0.6).max(8.) as u32;
+
+        for (n, &(i, j)) in stops.iter().enumerate() {
+            let Some(cell) = self.get(i, j) else {
+                continue;
+            };
+
+            draw.text(&(n + 1).to_string())
+                .xy(cell.pos)
+                .font_size(font_size)
+                .color(WHITE);
         }
     }
 
-    pub fn place(&mut self, position: Vec2, ctype: CellType) {
-        let i = position.x - self.x_offset;
-        let i = (i / CELL_SIDE).round() as usize;
-        let j = position.y - self.y_offset;
-        let j = (j / CELL_SIDE).round() as usize;
+    /// Draws a path as a line going through the center of its cells
+    pub fn draw_path(&self, draw: &Draw, path: &[(usize, usize)]) {
+        let points = path
+            .iter()
+            .filter_map(|&(i, j)| self.get(i, j))
+            .map(|cell| cell.pos);
 
-        if i >= self.w || j  >= self.h {
+        draw.polyline()
+            .weight(self.cell_size / 5.)
+            .join_round()
+            .color(*COLOR_PATH)
+            .points(points);
+    }
+
+    /// Cell under a board position
+    pub fn cell_at(&self, position: Vec2) -> Option<(usize, usize)> {
+        let i = (position.x - self.x_offset) / self.cell_size;
+        let j = (position.y - self.y_offset) / self.cell_size;
+        let (i, j) = (i.round(), j.round());
+
+        (i >= 0. && j >= 0. && (i as usize) < self.w && (j as usize) < self.h)
+            .then_some((i as usize, j as usize))
+    }
+
+    /// Cell under a window position seen through the camera
+    pub fn cell_under(
+        &self,
+        camera: &Camera,
+        position: Vec2,
+    ) -> Option<(usize, usize)> {
+        self.cell_at(camera.to_board(position))
+    }
+
+    /// Sets a cell, returns it if its type changed
+    pub fn place_cell(
+        &mut self,
+        i: usize,
+        j: usize,
+        ctype: CellType,
+    ) -> Option<(usize, usize)> {
+        if self.get(i, j)?.ctype == ctype {
+            return None;
+        }
+
+        self.set(i, j, ctype);
+        Some((i, j))
+    }
+
+    /// Changes the type of a cell. A new start removes the previous one
+    /// unless there are several agents, stops add up as goals or agents'
+    /// destinations.
+    pub fn set(&mut self, i: usize, j: usize, ctype: CellType) {
+        if i >= self.w || j >= self.h {
             return;
         }
 
-        // replace start or stop if needed
-        let a = match ctype {
-            CellType::Start => Some(0),
-            CellType::Stop => Some(1),
-            _ => None,
+        // forget the start or stop that gets overwritten
+        self.starts.retain(|&start| start != (i, j));
+        self.stops.retain(|&stop| stop != (i, j));
+
+        // there are as many stops as needed, but usually a single start
+        match ctype {
+            CellType::Start => {
+                if self.single_start {
+                    for (k, l) in std::mem::take(&mut self.starts) {
+                        self.get_mut(k, l).unwrap().ctype = CellType::Empty;
+                    }
+                }
+                self.starts.push((i, j));
+            }
+            CellType::Stop => self.stops.push((i, j)),
+            _ => (),
+        }
+
+        if let Some(cell) = self.get_mut(i, j) {
+            cell.ctype = ctype;
+        }
+    }
+
+    /// Sets every cell to the same type, start and stop included
+    pub fn fill(&mut self, ctype: CellType) {
+        for cell in self.board.iter_mut() {
+            cell.ctype = ctype;
+        }
+        self.starts.clear();
+        self.stops.clear();
+        self.touch_map();
+    }
+
+    /// Cells of the same type as a cell that can be reached from it
+    /// without diagonal moves
+    pub fn region(&self, i: usize, j: usize) -> Vec<(usize, usize)> {
+        let Some(ctype) = self.get(i, j).map(|cell| cell.ctype) else {
+            return Vec::new();
         };
+        let mut seen = vec![false; self.w * self.h];
+        let mut open = vec![(i, j)];
+        let mut region = Vec::new();
+        seen[i * self.h + j] = true;
 
-        if let Some(num) = a {
-            if let Some((k, l)) = self.start_stop[num] {
-                self.get_mut(k, l).unwrap().ctype = CellType::Empty;
+        while let Some((i, j)) = open.pop() {
+            region.push((i, j));
+            for (di, dj) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let (Some(k), Some(l)) =
+                    (i.checked_add_signed(di), j.checked_add_signed(dj))
+                else {
+                    continue;
+                };
+                if k >= self.w || l >= self.h || seen[k * self.h + l] {
+                    continue;
+                }
+                if self.board[k * self.h + l].ctype == ctype {
+                    seen[k * self.h + l] = true;
+                    open.push((k, l));
+                }
             }
-            self.start_stop[num] = Some((i, j));
         }
 
-        self.get_mut(i, j).map(|cell| cell.ctype = ctype);
+        region
     }
 }
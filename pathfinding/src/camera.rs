@@ -0,0 +1,50 @@
+/// Pan and zoom over the board, window positions go through it before
+/// reaching the terrain
+use nannou::prelude::*;
+
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 500.;
+
+pub struct Camera {
+    /// Where the origin of the board is in the window
+    pub pan: Vec2,
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera {
+            pan: Vec2::ZERO,
+            zoom: 1.,
+        }
+    }
+}
+
+impl Camera {
+    /// Draw context in board coordinates
+    pub fn transform(&self, draw: &Draw) -> Draw {
+        draw.xy(self.pan).scale(self.zoom)
+    }
+
+    /// Board position under a window position
+    pub fn to_board(&self, position: Vec2) -> Vec2 {
+        (position - self.pan) / self.zoom
+    }
+
+    /// Part of the board visible in the window
+    pub fn visible(&self, window: Rect) -> Rect {
+        let (bottom_left, top_right) = (
+            self.to_board(window.bottom_left()),
+            self.to_board(window.top_right()),
+        );
+
+        Rect::from_corners(bottom_left, top_right)
+    }
+
+    /// Zooms by a factor, keeping the board still under a window position
+    pub fn zoom_at(&mut self, position: Vec2, factor: f32) {
+        let anchor = self.to_board(position);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan = position - anchor * self.zoom;
+    }
+}
@@ -0,0 +1,218 @@
+/// Several algorithms solving copies of the same terrain in lockstep, each
+/// in its own viewport with its stats below
+use crate::board::{CellType, Terrain};
+use crate::heatmap::Heatmap;
+use crate::pathfinder::{AStarSettings, Algorithm, Pathfinder, StepResult};
+use crate::stats::{self, Stats, PANEL_HEIGHT};
+use crate::tool::Editor;
+use nannou::prelude::*;
+use once_cell::sync::Lazy;
+use std::time::Instant;
+
+/// Space between the viewports
+const MARGIN: f32 = 4.;
+
+static COLOR_BORDER: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0x00, 0x00, 0x00));
+
+struct Lane {
+    algorithm: Algorithm,
+    terrain: Terrain,
+    pathfinder: Box<dyn Pathfinder>,
+    /// None until the search starts
+    stats: Option<Stats>,
+    path: Vec<(usize, usize)>,
+}
+
+impl Lane {
+    fn is_searching(&self) -> bool {
+        self.stats
+            .as_ref()
+            .is_some_and(|stats| stats.result == StepResult::Running)
+    }
+}
+
+pub struct Comparison {
+    lanes: Vec<Lane>,
+    /// Board and stats areas of each lane
+    viewports: Vec<(Rect, Rect)>,
+}
+
+impl Comparison {
+    /// Dijkstra and A* side by side, or four algorithms from breadth first
+    /// to bidirectional A*
+    pub fn new(count: usize, terrain: &Terrain, window: Rect) -> Comparison {
+        let algorithms = match count {
+            2 => vec![Algorithm::Dijkstra, Algorithm::AStar],
+            _ => vec![
+                Algorithm::BreadthFirst,
+                Algorithm::Dijkstra,
+                Algorithm::AStar,
+                Algorithm::BidirectionalAStar,
+            ],
+        };
+        let settings = AStarSettings::default();
+
+        let mut comparison = Comparison {
+            lanes: algorithms
+                .into_iter()
+                .map(|algorithm| Lane {
+                    algorithm,
+                    terrain: terrain.clone(),
+                    pathfinder: algorithm.build(&settings),
+                    stats: None,
+                    path: Vec::new(),
+                })
+                .collect(),
+            viewports: viewports(window, count),
+        };
+        comparison.sync(terrain);
+
+        comparison
+    }
+
+    /// Copies the board into every lane, forgetting their searches
+    pub fn sync(&mut self, terrain: &Terrain) {
+        for (lane, (board, _)) in self.lanes.iter_mut().zip(&self.viewports) {
+            lane.terrain = terrain.clone();
+            lane.terrain.reset_search();
+            lane.terrain.fit(board.w(), board.h());
+            lane.stats = None;
+            lane.path.clear();
+        }
+    }
+
+    /// Lane whose viewport is under a window position
+    pub fn lane_at(&self, position: Vec2) -> Option<usize> {
+        self.viewports.iter().position(|(board, panel)| {
+            board.contains(position) || panel.contains(position)
+        })
+    }
+
+    /// Cell under a window position, in whichever viewport it is
+    pub fn cell_at(&self, position: Vec2) -> Option<(usize, usize)> {
+        let lane = self.lane_at(position)?;
+        let (board, _) = self.viewports[lane];
+
+        self.lanes[lane].terrain.cell_at(position - board.xy())
+    }
+
+    /// Gives the next algorithm to a lane
+    pub fn next_algorithm(&mut self, lane: usize) {
+        let lane = &mut self.lanes[lane];
+        lane.algorithm = lane.algorithm.next();
+        println!("Viewport now runs {:?}", lane.algorithm);
+    }
+
+    /// Starts every search from the start to the stops of the board,
+    /// returns false if one of them is missing
+    pub fn start(&mut self, settings: &AStarSettings) -> bool {
+        // every lane has the same board
+        let board = &self.lanes[0].terrain;
+        let Some(start) = board.start().filter(|_| !board.stops.is_empty())
+        else {
+            return false;
+        };
+
+        for lane in self.lanes.iter_mut() {
+            let terrain = &mut lane.terrain;
+            terrain.reset_search();
+            lane.pathfinder = lane.algorithm.build(settings);
+            lane.pathfinder.init(terrain, start, &terrain.stops);
+            lane.stats = Some(Stats::new(lane.pathfinder.as_ref()));
+            lane.path.clear();
+        }
+
+        true
+    }
+
+    /// Makes one step in every search still going, returns false once
+    /// they are all over
+    pub fn step(&mut self) -> bool {
+        for lane in self.lanes.iter_mut().filter(|lane| lane.is_searching()) {
+            let started = Instant::now();
+            let result = lane.pathfinder.step(&mut lane.terrain);
+            let elapsed = started.elapsed();
+
+            let stats = lane.stats.as_mut().unwrap();
+            stats.update(
+                lane.pathfinder.as_ref(),
+                &lane.terrain,
+                elapsed,
+                result,
+            );
+            if result == StepResult::Found {
+                lane.path = lane.pathfinder.path().unwrap_or_default();
+                stats.found(&lane.terrain, &lane.path);
+            }
+            if result != StepResult::Running {
+                println!("{}", stats.log_line());
+            }
+        }
+
+        self.lanes.iter().any(Lane::is_searching)
+    }
+
+    /// Draws every viewport, with the shape being dragged by the editor
+    pub fn draw(
+        &self,
+        app: &App,
+        draw: &Draw,
+        heatmap: Heatmap,
+        editor: &Editor,
+        ctype: CellType,
+    ) {
+        for (lane, &(board, panel)) in self.lanes.iter().zip(&self.viewports) {
+            // each copy is laid out around the origin, like the board
+            let area = board.pad(-MARGIN);
+            draw.rect().xy(area.xy()).wh(area.wh()).color(*COLOR_BORDER);
+
+            let viewport = draw.xy(board.xy());
+            let terrain = &lane.terrain;
+            let range = terrain.draw(app, &viewport, heatmap);
+            if terrain.cell_size() > 4. {
+                terrain.draw_grid(&viewport, board.shift(-board.xy()), 1.);
+            }
+            terrain.draw_path(&viewport, &lane.path);
+            editor.draw(&viewport, terrain, ctype);
+            heatmap.draw_legend(draw, range, board);
+
+            match &lane.stats {
+                Some(stats) => stats.draw_in(draw, panel),
+                None => stats::draw_lines(
+                    draw,
+                    panel,
+                    &[format!("{:?}", lane.algorithm)],
+                    1,
+                ),
+            }
+        }
+    }
+}
+
+/// Board and stats areas of each viewport, side by side for two of them,
+/// on two rows for four
+fn viewports(window: Rect, count: usize) -> Vec<(Rect, Rect)> {
+    let columns = count.min(2);
+    let rows = count.div_ceil(columns);
+    let (w, h) = (window.w() / columns as f32, window.h() / rows as f32);
+
+    (0..count)
+        .map(|n| {
+            let (column, row) = ((n % columns) as f32, (n / columns) as f32);
+            let area = Rect::from_x_y_w_h(
+                window.left() + w * (column + 0.5),
+                window.top() - h * (row + 0.5),
+                w,
+                h,
+            )
+            .pad(MARGIN);
+
+            let panel =
+                Rect::from_w_h(area.w(), PANEL_HEIGHT).mid_bottom_of(area);
+            let board = Rect::from_w_h(area.w(), area.h() - PANEL_HEIGHT)
+                .mid_top_of(area)
+                .pad(MARGIN);
+            (board, panel)
+        })
+        .collect()
+}
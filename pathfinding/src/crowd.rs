@@ -0,0 +1,147 @@
+/// Crowd of particles following a flow field to the stops
+use crate::board::Terrain;
+use crate::pathfinder::flow_field::FlowField;
+use nannou::prelude::*;
+use nannou::rand::rngs::StdRng;
+use nannou::rand::{Rng, SeedableRng};
+use once_cell::sync::Lazy;
+
+const PARTICLES: usize = 500;
+/// Particles added per update until the crowd is full
+const SPAWN_RATE: usize = 10;
+/// Cells crossed per second on a cell costing 1
+const SPEED: f32 = 4.;
+
+static COLOR_ARROW: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0x00, 0x00, 0x00, 0x60));
+static COLOR_PARTICLE: Lazy<Rgb8> = Lazy::new(|| Rgb8::new(0x9C, 0x27, 0xB0));
+
+struct Particle {
+    pos: Vec2,
+    /// Cell of the field the particle leaves from
+    cell: (usize, usize),
+    /// Where it aims within each cell, in cell sizes, so that the crowd
+    /// does not walk in single file
+    offset: Vec2,
+}
+
+pub struct Crowd {
+    field: FlowField,
+    /// Cells particles spawn on
+    spawns: Vec<(usize, usize)>,
+    particles: Vec<Particle>,
+    rng: StdRng,
+}
+
+impl Crowd {
+    pub fn new(terrain: &Terrain, seed: u64) -> Crowd {
+        let mut crowd = Crowd {
+            field: FlowField::new(terrain, &[]),
+            spawns: Vec::new(),
+            particles: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+        };
+        crowd.update_field(terrain);
+
+        crowd
+    }
+
+    /// Computes the field again after the terrain changed
+    pub fn update_field(&mut self, terrain: &Terrain) {
+        self.field = FlowField::new(terrain, &terrain.stops);
+        let field = &self.field;
+        self.spawns = field
+            .reachable()
+            .into_iter()
+            .filter(|&(i, j)| field.next(i, j).is_some())
+            .collect();
+
+        // particles cut off from every stop start over elsewhere
+        self.particles.retain(|particle| {
+            field.next(particle.cell.0, particle.cell.1).is_some()
+        });
+    }
+
+    fn spawn(&mut self, terrain: &Terrain) -> Option<Particle> {
+        let cell = self.spawns[self.rng.gen_range(0..self.spawns.len())];
+        let offset = Vec2::new(
+            self.rng.gen_range(-0.3..0.3),
+            self.rng.gen_range(-0.3..0.3),
+        );
+        let pos =
+            terrain.get(cell.0, cell.1)?.pos + offset * terrain.cell_size();
+
+        Some(Particle { pos, cell, offset })
+    }
+
+    /// Moves every particle for the elapsed seconds, the ones reaching a
+    /// stop are replaced by new ones
+    pub fn update(&mut self, terrain: &Terrain, elapsed: f32) {
+        if self.spawns.is_empty() {
+            return;
+        }
+        for _ in 0..SPAWN_RATE.min(PARTICLES - self.particles.len()) {
+            if let Some(particle) = self.spawn(terrain) {
+                self.particles.push(particle);
+            }
+        }
+
+        let field = &self.field;
+        let size = terrain.cell_size();
+        self.particles.retain_mut(|particle| {
+            let (i, j) = particle.cell;
+            let Some((k, l)) = field.next(i, j) else {
+                return false;
+            };
+
+            // slower on expensive cells
+            let speed = SPEED * size / terrain.cost(i, j).unwrap_or(1.);
+            let target =
+                terrain.get(k, l).unwrap().pos + particle.offset * size;
+            let step =
+                (target - particle.pos).clamp_length_max(speed * elapsed);
+            particle.pos += step;
+
+            // the next cell is only taken once the particle is inside it,
+            // not when it grazes a corner on the way
+            if terrain.cell_at(particle.pos) == Some((k, l)) {
+                particle.cell = (k, l);
+            }
+            true
+        });
+    }
+
+    /// Draws an arrow on every cell that leads somewhere, then the particles
+    pub fn draw(&self, draw: &Draw, terrain: &Terrain) {
+        let size = terrain.cell_size();
+
+        // arrows would only blur tiny cells
+        if size >= 8. {
+            for i in 0..terrain.width() {
+                for j in 0..terrain.height() {
+                    let Some((x, y)) = self.field.direction(i, j) else {
+                        continue;
+                    };
+                    let center = terrain.get(i, j).unwrap().pos;
+                    let half = Vec2::new(x, y) * size * 0.3;
+
+                    draw.arrow()
+                        .start(center - half)
+                        .end(center + half)
+                        .weight(size / 16.)
+                        .head_length(size / 6.)
+                        .head_width(size / 8.)
+                        .color(*COLOR_ARROW);
+                }
+            }
+        }
+
+        let radius = (size * 0.15).max(1.5);
+        for particle in &self.particles {
+            draw.ellipse()
+                .xy(particle.pos)
+                .radius(radius)
+                .color(*COLOR_PARTICLE);
+        }
+    }
+}
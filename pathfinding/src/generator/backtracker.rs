@@ -0,0 +1,75 @@
+/// Recursive backtracker, a random depth first walk giving long corridors
+use super::{carve, carve_to, Generator, Rooms};
+use crate::board::{CellType, Terrain};
+use nannou::rand::rngs::StdRng;
+use nannou::rand::seq::SliceRandom;
+use nannou::rand::{Rng, SeedableRng};
+
+pub struct Backtracker {
+    rng: StdRng,
+    rooms: Rooms,
+    visited: Vec<bool>,
+    stack: Vec<(usize, usize)>,
+}
+
+impl Backtracker {
+    pub fn new(seed: u64) -> Backtracker {
+        Backtracker {
+            rng: StdRng::seed_from_u64(seed),
+            rooms: Rooms::default(),
+            visited: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+}
+
+impl Generator for Backtracker {
+    fn name(&self) -> String {
+        "Recursive backtracker".to_string()
+    }
+
+    fn init(&mut self, terrain: &mut Terrain) {
+        terrain.fill(CellType::Wall);
+        self.rooms = Rooms::of(terrain);
+        self.visited = vec![false; self.rooms.count()];
+        self.stack.clear();
+
+        if self.rooms.count() == 0 {
+            return;
+        }
+
+        let start = (
+            self.rng.gen_range(0..self.rooms.w),
+            self.rng.gen_range(0..self.rooms.h),
+        );
+        carve(terrain, Rooms::cell(start));
+        self.visited[self.rooms.index(start)] = true;
+        self.stack.push(start);
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> bool {
+        let Some(&current) = self.stack.last() else {
+            return false;
+        };
+
+        let options: Vec<(usize, usize)> = self
+            .rooms
+            .neighbours(current)
+            .into_iter()
+            .filter(|&room| !self.visited[self.rooms.index(room)])
+            .collect();
+
+        match options.choose(&mut self.rng) {
+            Some(&next) => {
+                carve_to(terrain, current, next);
+                self.visited[self.rooms.index(next)] = true;
+                self.stack.push(next);
+            }
+            None => {
+                self.stack.pop();
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,93 @@
+/// Caves grown by a cellular automaton
+use crate::board::{CellType, Terrain};
+use nannou::rand::rngs::StdRng;
+use nannou::rand::{Rng, SeedableRng};
+
+/// Rules of the automaton, counts are of the 8 surrounding walls
+pub struct CaveRules {
+    /// Probability for a cell to start as a wall
+    pub fill: f64,
+    /// Wall counts turning an open cell into a wall
+    pub birth: Vec<usize>,
+    /// Wall counts keeping a wall as it is
+    pub survival: Vec<usize>,
+    pub iterations: usize,
+}
+
+impl Default for CaveRules {
+    /// B5678/S45678, the usual rule for caves
+    fn default() -> CaveRules {
+        CaveRules {
+            fill: 0.45,
+            birth: vec![5, 6, 7, 8],
+            survival: vec![4, 5, 6, 7, 8],
+            iterations: 5,
+        }
+    }
+}
+
+/// Replaces the whole board with a cave
+pub fn generate(terrain: &mut Terrain, seed: u64, rules: &CaveRules) {
+    let (w, h) = (terrain.width(), terrain.height());
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut walls: Vec<bool> =
+        (0..w * h).map(|_| rng.gen_bool(rules.fill)).collect();
+
+    for _ in 0..rules.iterations {
+        let mut next = walls.clone();
+
+        for i in 0..w {
+            for j in 0..h {
+                let count = surrounding_walls(&walls, w, h, i, j);
+                next[i * h + j] = if walls[i * h + j] {
+                    rules.survival.contains(&count)
+                } else {
+                    rules.birth.contains(&count)
+                };
+            }
+        }
+
+        walls = next;
+    }
+
+    terrain.fill(CellType::Empty);
+    for i in 0..w {
+        for j in 0..h {
+            if walls[i * h + j] {
+                terrain.set(i, j, CellType::Wall);
+            }
+        }
+    }
+}
+
+/// Walls around a cell, the outside of the board counts as walls
+fn surrounding_walls(
+    walls: &[bool],
+    w: usize,
+    h: usize,
+    i: usize,
+    j: usize,
+) -> usize {
+    let mut count = 0;
+
+    for di in -1..=1 {
+        for dj in -1..=1 {
+            if di == 0 && dj == 0 {
+                continue;
+            }
+
+            let neighbour = i
+                .checked_add_signed(di)
+                .zip(j.checked_add_signed(dj))
+                .filter(|&(k, l)| k < w && l < h);
+
+            match neighbour {
+                Some((k, l)) if !walls[k * h + l] => (),
+                _ => count += 1,
+            }
+        }
+    }
+
+    count
+}
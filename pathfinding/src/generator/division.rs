@@ -0,0 +1,93 @@
+/// Recursive division, splits open chambers with walls having a single gap
+use super::{Generator, Rooms};
+use crate::board::{CellType, Terrain};
+use nannou::rand::rngs::StdRng;
+use nannou::rand::{Rng, SeedableRng};
+
+/// Part of the maze still to divide, in rooms: x, y, width, height
+type Chamber = (usize, usize, usize, usize);
+
+pub struct Division {
+    rng: StdRng,
+    chambers: Vec<Chamber>,
+}
+
+impl Division {
+    pub fn new(seed: u64) -> Division {
+        Division {
+            rng: StdRng::seed_from_u64(seed),
+            chambers: Vec::new(),
+        }
+    }
+}
+
+impl Generator for Division {
+    fn name(&self) -> String {
+        "Recursive division".to_string()
+    }
+
+    fn init(&mut self, terrain: &mut Terrain) {
+        let rooms = Rooms::of(terrain);
+        terrain.fill(CellType::Empty);
+
+        // walls all around the rooms
+        for i in 0..terrain.width() {
+            for j in 0..terrain.height() {
+                if i == 0 || j == 0 || i >= 2 * rooms.w || j >= 2 * rooms.h {
+                    terrain.set(i, j, CellType::Wall);
+                }
+            }
+        }
+
+        self.chambers = vec![(0, 0, rooms.w, rooms.h)];
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> bool {
+        // chambers of a single row or column are already done
+        let (x, y, w, h) = loop {
+            match self.chambers.pop() {
+                Some((_, _, w, h)) if w < 2 && h < 2 => continue,
+                Some(chamber) => break chamber,
+                None => return false,
+            }
+        };
+
+        let horizontal = if w < 2 {
+            true
+        } else if h < 2 {
+            false
+        } else if w == h {
+            self.rng.gen_bool(0.5)
+        } else {
+            h > w
+        };
+
+        if horizontal {
+            let k = self.rng.gen_range(y + 1..y + h);
+            let gap = self.rng.gen_range(x..x + w);
+
+            for i in 2 * x..=2 * (x + w) {
+                if i != 2 * gap + 1 {
+                    terrain.set(i, 2 * k, CellType::Wall);
+                }
+            }
+
+            self.chambers.push((x, y, w, k - y));
+            self.chambers.push((x, k, w, y + h - k));
+        } else {
+            let k = self.rng.gen_range(x + 1..x + w);
+            let gap = self.rng.gen_range(y..y + h);
+
+            for j in 2 * y..=2 * (y + h) {
+                if j != 2 * gap + 1 {
+                    terrain.set(2 * k, j, CellType::Wall);
+                }
+            }
+
+            self.chambers.push((x, y, k - x, h));
+            self.chambers.push((k, y, x + w - k, h));
+        }
+
+        true
+    }
+}
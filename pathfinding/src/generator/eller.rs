@@ -0,0 +1,97 @@
+/// Eller's algorithm, builds the maze one row at a time
+use super::{carve, Generator, Rooms};
+use crate::board::{CellType, Terrain};
+use nannou::rand::rngs::StdRng;
+use nannou::rand::seq::SliceRandom;
+use nannou::rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+pub struct Eller {
+    rng: StdRng,
+    rooms: Rooms,
+    row: usize,
+    /// Set of every room of the current row, rooms of a set are connected
+    sets: Vec<Option<usize>>,
+    next_set: usize,
+}
+
+impl Eller {
+    pub fn new(seed: u64) -> Eller {
+        Eller {
+            rng: StdRng::seed_from_u64(seed),
+            rooms: Rooms::default(),
+            row: 0,
+            sets: Vec::new(),
+            next_set: 0,
+        }
+    }
+}
+
+impl Generator for Eller {
+    fn name(&self) -> String {
+        "Eller".to_string()
+    }
+
+    fn init(&mut self, terrain: &mut Terrain) {
+        terrain.fill(CellType::Wall);
+        self.rooms = Rooms::of(terrain);
+        self.row = 0;
+        self.sets = vec![None; self.rooms.w];
+        self.next_set = 0;
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> bool {
+        let y = self.row;
+        if y >= self.rooms.h {
+            return false;
+        }
+        let last_row = y + 1 == self.rooms.h;
+
+        // rooms not connected from below start their own set
+        for x in 0..self.rooms.w {
+            carve(terrain, Rooms::cell((x, y)));
+            if self.sets[x].is_none() {
+                self.sets[x] = Some(self.next_set);
+                self.next_set += 1;
+            }
+        }
+
+        // randomly join neighbours, the last row has to join everything
+        for x in 0..self.rooms.w.saturating_sub(1) {
+            let (a, b) = (self.sets[x], self.sets[x + 1]);
+            if a == b || !(last_row || self.rng.gen_bool(0.5)) {
+                continue;
+            }
+
+            carve(terrain, Rooms::between((x, y), (x + 1, y)));
+            for set in self.sets.iter_mut() {
+                if *set == b {
+                    *set = a;
+                }
+            }
+        }
+
+        if !last_row {
+            let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+            for (x, set) in self.sets.iter().enumerate() {
+                groups.entry(set.unwrap()).or_default().push(x);
+            }
+
+            // every set goes down at least once
+            let mut below = vec![None; self.rooms.w];
+            for (set, mut xs) in groups {
+                xs.shuffle(&mut self.rng);
+                let count = self.rng.gen_range(1..=xs.len());
+
+                for &x in &xs[..count] {
+                    carve(terrain, Rooms::between((x, y), (x, y + 1)));
+                    below[x] = Some(set);
+                }
+            }
+            self.sets = below;
+        }
+
+        self.row += 1;
+        true
+    }
+}
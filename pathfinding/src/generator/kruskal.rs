@@ -0,0 +1,85 @@
+/// Randomized Kruskal, opens random walls between rooms not yet connected
+use super::{carve, Generator, Rooms};
+use crate::board::{CellType, Terrain};
+use nannou::rand::rngs::StdRng;
+use nannou::rand::seq::SliceRandom;
+use nannou::rand::SeedableRng;
+
+pub struct Kruskal {
+    rng: StdRng,
+    rooms: Rooms,
+    /// Union-find over the rooms
+    parents: Vec<usize>,
+    walls: Vec<((usize, usize), (usize, usize))>,
+}
+
+impl Kruskal {
+    pub fn new(seed: u64) -> Kruskal {
+        Kruskal {
+            rng: StdRng::seed_from_u64(seed),
+            rooms: Rooms::default(),
+            parents: Vec::new(),
+            walls: Vec::new(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // path compression
+        let mut index = index;
+        while self.parents[index] != root {
+            let next = self.parents[index];
+            self.parents[index] = root;
+            index = next;
+        }
+
+        root
+    }
+}
+
+impl Generator for Kruskal {
+    fn name(&self) -> String {
+        "Randomized Kruskal".to_string()
+    }
+
+    fn init(&mut self, terrain: &mut Terrain) {
+        terrain.fill(CellType::Wall);
+        self.rooms = Rooms::of(terrain);
+        self.parents = (0..self.rooms.count()).collect();
+        self.walls.clear();
+
+        for x in 0..self.rooms.w {
+            for y in 0..self.rooms.h {
+                carve(terrain, Rooms::cell((x, y)));
+
+                if x + 1 < self.rooms.w {
+                    self.walls.push(((x, y), (x + 1, y)));
+                }
+                if y + 1 < self.rooms.h {
+                    self.walls.push(((x, y), (x, y + 1)));
+                }
+            }
+        }
+
+        self.walls.shuffle(&mut self.rng);
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> bool {
+        while let Some((a, b)) = self.walls.pop() {
+            let root_a = self.find(self.rooms.index(a));
+            let root_b = self.find(self.rooms.index(b));
+
+            if root_a != root_b {
+                self.parents[root_a] = root_b;
+                carve(terrain, Rooms::between(a, b));
+                return true;
+            }
+        }
+
+        false
+    }
+}
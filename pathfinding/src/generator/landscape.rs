@@ -0,0 +1,67 @@
+/// Landscapes made of weighted terrains, following a noise map
+use crate::board::{CellType, Terrain};
+use nannou::noise::{Fbm, MultiFractal, NoiseFn, Seedable, Value};
+
+/// Size of the noise features, in cells
+const SCALE: f64 = 12.;
+const OCTAVES: usize = 3;
+
+/// Cell types from the lowest to the highest noise values,
+/// each one is used below its threshold
+const LEVELS: [(f64, CellType); 6] = [
+    (-0.35, CellType::Water),
+    (-0.2, CellType::Sand),
+    (0.1, CellType::Empty),
+    (0.3, CellType::Grass),
+    (0.45, CellType::Mud),
+    (f64::INFINITY, CellType::Wall),
+];
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum NoiseKind {
+    Perlin,
+    Value,
+}
+
+/// Replaces the whole board with a landscape
+pub fn generate(terrain: &mut Terrain, seed: u64, kind: NoiseKind) {
+    // the same seed always gives the same landscape, a single octave of
+    // Fbm is Perlin noise, whose name two types share in noise 0.7
+    let seed = seed as u32;
+    let noise: Box<dyn NoiseFn<[f64; 2]>> = match kind {
+        NoiseKind::Perlin => Box::new(Fbm::new().set_octaves(1).set_seed(seed)),
+        NoiseKind::Value => Box::new(Value::new().set_seed(seed)),
+    };
+
+    terrain.fill(CellType::Empty);
+
+    for i in 0..terrain.width() {
+        for j in 0..terrain.height() {
+            let value = fractal(noise.as_ref(), i as f64, j as f64);
+            let ctype = LEVELS
+                .iter()
+                .find(|(threshold, _)| value < *threshold)
+                .map(|&(_, ctype)| ctype)
+                .unwrap();
+
+            terrain.set(i, j, ctype);
+        }
+    }
+}
+
+/// Sum of a few octaves of noise, stays around [-1, 1]
+fn fractal(noise: &dyn NoiseFn<[f64; 2]>, x: f64, y: f64) -> f64 {
+    let mut value = 0.;
+    let mut amplitude = 1.;
+    let mut frequency = 1. / SCALE;
+    let mut total = 0.;
+
+    for _ in 0..OCTAVES {
+        value += amplitude * noise.get([x * frequency, y * frequency]);
+        total += amplitude;
+        amplitude /= 2.;
+        frequency *= 2.;
+    }
+
+    value / total
+}
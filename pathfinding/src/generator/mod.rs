@@ -0,0 +1,189 @@
+/// Map generators, mazes are carved one step at a time,
+/// caves and landscapes are written at once
+mod backtracker;
+pub mod cave;
+mod division;
+mod eller;
+mod kruskal;
+pub mod landscape;
+mod prim;
+
+pub use backtracker::Backtracker;
+pub use division::Division;
+pub use eller::Eller;
+pub use kruskal::Kruskal;
+pub use prim::Prim;
+
+use crate::board::{CellType, Terrain};
+use std::collections::VecDeque;
+
+/// A generator that can be advanced one step at a time.
+/// The same seed always gives the same maze.
+pub trait Generator {
+    fn name(&self) -> String;
+
+    fn init(&mut self, terrain: &mut Terrain);
+
+    /// Returns false once the maze is done
+    fn step(&mut self, terrain: &mut Terrain) -> bool;
+}
+
+/// Generators that can be selected at runtime
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MazeAlgorithm {
+    Backtracker,
+    Prim,
+    Kruskal,
+    Eller,
+    Division,
+}
+
+impl MazeAlgorithm {
+    pub fn build(&self, seed: u64) -> Box<dyn Generator> {
+        match self {
+            MazeAlgorithm::Backtracker => Box::new(Backtracker::new(seed)),
+            MazeAlgorithm::Prim => Box::new(Prim::new(seed)),
+            MazeAlgorithm::Kruskal => Box::new(Kruskal::new(seed)),
+            MazeAlgorithm::Eller => Box::new(Eller::new(seed)),
+            MazeAlgorithm::Division => Box::new(Division::new(seed)),
+        }
+    }
+
+    pub fn next(&self) -> MazeAlgorithm {
+        match self {
+            MazeAlgorithm::Backtracker => MazeAlgorithm::Prim,
+            MazeAlgorithm::Prim => MazeAlgorithm::Kruskal,
+            MazeAlgorithm::Kruskal => MazeAlgorithm::Eller,
+            MazeAlgorithm::Eller => MazeAlgorithm::Division,
+            MazeAlgorithm::Division => MazeAlgorithm::Backtracker,
+        }
+    }
+}
+
+/// Rooms of a maze are the cells with odd coordinates,
+/// the cells between two rooms are either walls or passages
+#[derive(Copy, Clone, Default)]
+pub struct Rooms {
+    pub w: usize,
+    pub h: usize,
+}
+
+impl Rooms {
+    pub fn of(terrain: &Terrain) -> Rooms {
+        Rooms {
+            w: terrain.width().saturating_sub(1) / 2,
+            h: terrain.height().saturating_sub(1) / 2,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.w * self.h
+    }
+
+    pub fn index(&self, (x, y): (usize, usize)) -> usize {
+        x * self.h + y
+    }
+
+    /// Cell of the terrain holding the room
+    pub fn cell((x, y): (usize, usize)) -> (usize, usize) {
+        (2 * x + 1, 2 * y + 1)
+    }
+
+    /// Cell of the terrain between two neighbouring rooms
+    pub fn between(a: (usize, usize), b: (usize, usize)) -> (usize, usize) {
+        (a.0 + b.0 + 1, a.1 + b.1 + 1)
+    }
+
+    pub fn neighbours(&self, (x, y): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut res = Vec::new();
+
+        if x > 0 {
+            res.push((x - 1, y));
+        }
+        if y > 0 {
+            res.push((x, y - 1));
+        }
+        if x + 1 < self.w {
+            res.push((x + 1, y));
+        }
+        if y + 1 < self.h {
+            res.push((x, y + 1));
+        }
+
+        res
+    }
+}
+
+/// Opens a cell of the terrain
+pub fn carve(terrain: &mut Terrain, (i, j): (usize, usize)) {
+    terrain.set(i, j, CellType::Empty);
+}
+
+/// Opens the passage between two rooms, and the second room
+pub fn carve_to(
+    terrain: &mut Terrain,
+    from: (usize, usize),
+    to: (usize, usize),
+) {
+    carve(terrain, Rooms::between(from, to));
+    carve(terrain, Rooms::cell(to));
+}
+
+/// Puts the start in the bottom left room and the stop in the top right one
+pub fn place_start_stop(terrain: &mut Terrain) {
+    let rooms = Rooms::of(terrain);
+    if rooms.count() < 2 {
+        return;
+    }
+
+    let (i, j) = Rooms::cell((0, 0));
+    terrain.set(i, j, CellType::Start);
+    let (i, j) = Rooms::cell((rooms.w - 1, rooms.h - 1));
+    terrain.set(i, j, CellType::Stop);
+}
+
+/// Walls every open cell that is not part of the largest open region,
+/// regions being linked the way the terrain links neighbours
+pub fn keep_largest_region(terrain: &mut Terrain) {
+    let (w, h) = (terrain.width(), terrain.height());
+    let mut regions: Vec<Option<usize>> = vec![None; w * h];
+    let mut sizes: Vec<usize> = Vec::new();
+
+    for i in 0..w {
+        for j in 0..h {
+            if regions[i * h + j].is_some() || !terrain.is_walkable(i, j) {
+                continue;
+            }
+
+            // flood fill a new region
+            let region = sizes.len();
+            let mut size = 0;
+            let mut queue = VecDeque::from([(i, j)]);
+            regions[i * h + j] = Some(region);
+
+            while let Some((k, l)) = queue.pop_front() {
+                size += 1;
+
+                for (x, y) in terrain.get_neighbours(k, l) {
+                    if regions[x * h + y].is_none() && terrain.is_walkable(x, y)
+                    {
+                        regions[x * h + y] = Some(region);
+                        queue.push_back((x, y));
+                    }
+                }
+            }
+
+            sizes.push(size);
+        }
+    }
+
+    let largest = (0..sizes.len()).max_by_key(|&region| sizes[region]);
+
+    for i in 0..w {
+        for j in 0..h {
+            if regions[i * h + j].is_some() && regions[i * h + j] != largest {
+                terrain.set(i, j, CellType::Wall);
+            }
+        }
+    }
+}
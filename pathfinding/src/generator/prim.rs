@@ -0,0 +1,74 @@
+/// Randomized Prim, grows the maze from random walls of its border
+use super::{carve, carve_to, Generator, Rooms};
+use crate::board::{CellType, Terrain};
+use nannou::rand::rngs::StdRng;
+use nannou::rand::{Rng, SeedableRng};
+
+pub struct Prim {
+    rng: StdRng,
+    rooms: Rooms,
+    visited: Vec<bool>,
+    /// Walls between a room of the maze and one of its neighbours
+    walls: Vec<((usize, usize), (usize, usize))>,
+}
+
+impl Prim {
+    pub fn new(seed: u64) -> Prim {
+        Prim {
+            rng: StdRng::seed_from_u64(seed),
+            rooms: Rooms::default(),
+            visited: Vec::new(),
+            walls: Vec::new(),
+        }
+    }
+
+    fn add_room(&mut self, room: (usize, usize)) {
+        self.visited[self.rooms.index(room)] = true;
+
+        for next in self.rooms.neighbours(room) {
+            if !self.visited[self.rooms.index(next)] {
+                self.walls.push((room, next));
+            }
+        }
+    }
+}
+
+impl Generator for Prim {
+    fn name(&self) -> String {
+        "Randomized Prim".to_string()
+    }
+
+    fn init(&mut self, terrain: &mut Terrain) {
+        terrain.fill(CellType::Wall);
+        self.rooms = Rooms::of(terrain);
+        self.visited = vec![false; self.rooms.count()];
+        self.walls.clear();
+
+        if self.rooms.count() == 0 {
+            return;
+        }
+
+        let start = (
+            self.rng.gen_range(0..self.rooms.w),
+            self.rng.gen_range(0..self.rooms.h),
+        );
+        carve(terrain, Rooms::cell(start));
+        self.add_room(start);
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> bool {
+        // walls leading back into the maze are dropped until one can be opened
+        while !self.walls.is_empty() {
+            let index = self.rng.gen_range(0..self.walls.len());
+            let (from, to) = self.walls.swap_remove(index);
+
+            if !self.visited[self.rooms.index(to)] {
+                carve_to(terrain, from, to);
+                self.add_room(to);
+                return true;
+            }
+        }
+
+        false
+    }
+}
@@ -1,8 +1,19 @@
 /// Handle events
 use nannou::prelude::*;
 use nannou::winit::event;
+use std::path::Path;
 
-use super::{Model, State};
+use super::board::CellType;
+use super::camera::Camera;
+use super::generator::landscape::NoiseKind;
+use super::{
+    advance, compare, generate_map, load_file, reset_search, save_file,
+    start_generating, start_walking, step_back, toggle_flowing, Model, State,
+    TICKS,
+};
+
+/// Zoom factor of a wheel notch
+const ZOOM_STEP: f32 = 1.2;
 
 pub fn handle_click(
     _app: &App,
@@ -10,21 +21,54 @@ pub fn handle_click(
     button: u32,
     state: event::ElementState,
 ) {
-    if model.state != State::Building {
+    if !model.can_edit() {
         return;
     }
 
     if let event::ElementState::Pressed = state {
         match button {
-            1 => (),                                               // left
-            2 => model.state = State::Initializing, // scroll wheel press
+            1 => (), // left
+            // scroll wheel press, a search replaces the crowd
+            2 => {
+                model.crowd = None;
+                model.state = State::Initializing;
+            }
             3 => model.selected_cell = model.selected_cell.next(), // right
             a => println!("Pressed {}", a),
         }
     }
 }
 
-pub fn handle_wheel(model: &mut Model, delta: event::MouseScrollDelta) {
+/// A .map file or an image dropped on the window replaces the board
+pub fn handle_drop(app: &App, model: &mut Model, path: &Path) {
+    if model.state != State::Building {
+        println!("Maps can only be loaded while building");
+        return;
+    }
+
+    load_file(app, model, path);
+    if let Some(comparison) = model.comparison.as_mut() {
+        comparison.sync(&model.terrain);
+    }
+}
+
+pub fn handle_wheel(
+    app: &App,
+    model: &mut Model,
+    delta: event::MouseScrollDelta,
+) {
+    // control and the wheel zooms around the mouse
+    if app.keys.mods.ctrl() && model.comparison.is_none() {
+        let y = match delta {
+            event::MouseScrollDelta::LineDelta(_x, y) => y,
+            event::MouseScrollDelta::PixelDelta(delta) => delta.y as f32 / 50.,
+        };
+        model
+            .camera
+            .zoom_at(app.mouse.position(), ZOOM_STEP.powf(y));
+        return;
+    }
+
     match delta {
         event::MouseScrollDelta::LineDelta(_x, y) => {
             if y < 0. {
@@ -37,3 +81,175 @@ pub fn handle_wheel(model: &mut Model, delta: event::MouseScrollDelta) {
         event::MouseScrollDelta::PixelDelta(_delta) => {}
     }
 }
+
+pub fn handle_key(app: &App, model: &mut Model, key: Key) {
+    if model.state == State::Flowing && key == Key::O {
+        toggle_flowing(model);
+        return;
+    }
+    // playback and colours, whatever is going on
+    match key {
+        Key::E => {
+            model.heatmap = model.heatmap.next();
+            println!("Heatmap: {:?}", model.heatmap);
+            return;
+        }
+        Key::Space => {
+            model.paused = !model.paused;
+            println!("Paused: {}", model.paused);
+            return;
+        }
+        Key::Right => {
+            model.paused = true;
+            advance(model, 1);
+            return;
+        }
+        Key::Left => {
+            step_back(model);
+            return;
+        }
+        Key::Up | Key::Down => {
+            model.speed = match key {
+                Key::Up => model.speed.saturating_sub(1),
+                _ => (model.speed + 1).min(TICKS.len() - 1),
+            };
+            println!("{:?} between steps", TICKS[model.speed]);
+            return;
+        }
+        Key::Key0 => {
+            model.camera = Camera::default();
+            return;
+        }
+        Key::B => {
+            model.editor.tool = model.editor.tool.next();
+            println!("Tool: {:?}", model.editor.tool);
+            return;
+        }
+        Key::LBracket | Key::RBracket => {
+            match key {
+                Key::LBracket => model.editor.shrink(),
+                _ => model.editor.grow(),
+            }
+            println!("Brush radius: {}", model.editor.radius);
+            return;
+        }
+        _ => (),
+    }
+    if model.state != State::Building {
+        return;
+    }
+    if model.comparison.is_some() && matches!(key, Key::F | Key::O) {
+        println!("Not available while comparing algorithms");
+        return;
+    }
+
+    match key {
+        Key::R => reset_search(model),
+        Key::G => start_generating(model),
+        Key::K => generate_map(model, None),
+        Key::P => generate_map(model, Some(NoiseKind::Perlin)),
+        Key::V => generate_map(model, Some(NoiseKind::Value)),
+        Key::M => {
+            model.maze = model.maze.next();
+            println!("Selected {:?} maze", model.maze);
+        }
+        Key::N => {
+            model.seed += 1;
+            println!("Seed is now {}", model.seed);
+        }
+        Key::Z | Key::Y if app.keys.mods.ctrl() => {
+            let (done, action) = match key {
+                Key::Z => (model.undo.undo(&mut model.terrain), "undo"),
+                _ => (model.undo.redo(&mut model.terrain), "redo"),
+            };
+            if done {
+                reset_search(model);
+            } else {
+                println!("Nothing to {}", action);
+            }
+        }
+        Key::S => save_file(model, &model.map_file),
+        // an image of the board next to the map file
+        Key::I => save_file(model, &model.map_file.with_extension("png")),
+        Key::L => load_file(app, model, &model.map_file.clone()),
+        Key::Key1 => compare(model, 1, app.window_rect()),
+        Key::Key2 => compare(model, 2, app.window_rect()),
+        Key::Key4 => compare(model, 4, app.window_rect()),
+        // the viewport under the mouse when comparing
+        Key::A => match &mut model.comparison {
+            Some(comparison) => {
+                if let Some(lane) = comparison.lane_at(app.mouse.position()) {
+                    comparison.next_algorithm(lane);
+                }
+            }
+            None => {
+                model.algorithm = model.algorithm.next();
+                println!("Selected {:?}", model.algorithm);
+            }
+        },
+        Key::H => {
+            model.astar.heuristic = model.astar.heuristic.next();
+            println!("A* heuristic: {:?}", model.astar.heuristic);
+        }
+        Key::W => {
+            model.astar.next_weight();
+            println!("A* heuristic weight: {}", model.astar.weight);
+        }
+        Key::C => {
+            model.terrain.connectivity = model.terrain.connectivity.next();
+            println!("Connectivity: {:?}", model.terrain.connectivity);
+        }
+        Key::X => {
+            model.terrain.corner_cutting = model.terrain.corner_cutting.next();
+            println!("Corner cutting: {:?}", model.terrain.corner_cutting);
+        }
+        Key::F => start_walking(model),
+        Key::O => toggle_flowing(model),
+        Key::U => {
+            let terrain = &mut model.terrain;
+            terrain.single_start = !terrain.single_start;
+
+            // only the last start is kept when back to a single agent, the
+            // removed ones can be undone like any edit
+            if terrain.single_start && terrain.starts.len() > 1 {
+                model.undo.begin(terrain);
+                for (i, j) in terrain.starts.clone().into_iter().rev().skip(1) {
+                    terrain.set(i, j, CellType::Empty);
+                }
+                model.undo.end(terrain);
+            }
+            println!("Several agents: {}", !terrain.single_start);
+        }
+        Key::Q => {
+            model.goal_mode = model.goal_mode.next();
+            println!("Several stops: {:?}", model.goal_mode);
+        }
+        Key::D => {
+            model.replanning = !model.replanning;
+            println!("Live replanning: {}", model.replanning);
+        }
+        Key::T => {
+            model.astar.tie_break = model.astar.tie_break.next();
+            println!("A* tie-breaking: {:?}", model.astar.tie_break);
+        }
+        _ => (),
+    }
+
+    // viewports follow the changes made to the board
+    let edited = matches!(
+        key,
+        Key::R
+            | Key::K
+            | Key::P
+            | Key::V
+            | Key::L
+            | Key::C
+            | Key::X
+            | Key::U
+            | Key::Z
+            | Key::Y
+    );
+    if let Some(comparison) = model.comparison.as_mut().filter(|_| edited) {
+        comparison.sync(&model.terrain);
+    }
+}
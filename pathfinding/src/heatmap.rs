@@ -0,0 +1,172 @@
+/// Heatmaps of the last search, visited cells coloured by what the search
+/// knew of them instead of a flat colour
+use crate::board::{CellType, Expansion, Terrain};
+use nannou::prelude::*;
+use once_cell::sync::Lazy;
+
+/// Steps of the legend gradient
+const LEGEND_STEPS: usize = 32;
+
+/// From the lowest to the highest value
+static COLOR_HEAT: Lazy<[Rgb8; 5]> = Lazy::new(|| {
+    [
+        Rgb8::new(0x44, 0x01, 0x54),
+        Rgb8::new(0x3B, 0x52, 0x8B),
+        Rgb8::new(0x21, 0x91, 0x8C),
+        Rgb8::new(0x5E, 0xC9, 0x62),
+        Rgb8::new(0xFD, 0xE7, 0x25),
+    ]
+});
+static COLOR_LEGEND: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0x00, 0x00, 0x00, 0xB0));
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Heatmap {
+    Off,
+    /// Cost from where the search started, g
+    Cost,
+    /// When the cell was expanded
+    Order,
+    /// Cost plus heuristic, f
+    Estimate,
+}
+
+impl Heatmap {
+    pub fn next(&self) -> Heatmap {
+        match self {
+            Heatmap::Off => Heatmap::Cost,
+            Heatmap::Cost => Heatmap::Order,
+            Heatmap::Order => Heatmap::Estimate,
+            Heatmap::Estimate => Heatmap::Off,
+        }
+    }
+
+    fn value(&self, expansion: &Expansion) -> Option<f32> {
+        match self {
+            Heatmap::Off => None,
+            Heatmap::Cost => Some(expansion.g),
+            Heatmap::Order => Some(expansion.order as f32),
+            Heatmap::Estimate => Some(expansion.f),
+        }
+    }
+
+    fn title(&self) -> &str {
+        match self {
+            Heatmap::Off => "",
+            Heatmap::Cost => "g-cost",
+            Heatmap::Order => "expansion order",
+            Heatmap::Estimate => "f-cost",
+        }
+    }
+
+    /// Lowest and highest value over the expanded cells, None when off
+    pub fn range(&self, terrain: &Terrain) -> Option<(f32, f32)> {
+        if *self == Heatmap::Off {
+            return None;
+        }
+
+        (0..terrain.width())
+            .flat_map(|i| (0..terrain.height()).map(move |j| (i, j)))
+            .filter_map(|(i, j)| self.value(&terrain.expansion(i, j)?))
+            .fold(None, |range, value| match range {
+                None => Some((value, value)),
+                Some((low, high)) => Some((low.min(value), high.max(value))),
+            })
+    }
+
+    /// Colour of each expanded cell within the range, for the texture of
+    /// the board
+    pub fn colors<'a>(
+        &'a self,
+        terrain: &'a Terrain,
+        range: Option<(f32, f32)>,
+    ) -> impl Fn(usize, usize) -> Option<Rgb8> + 'a {
+        move |i, j| {
+            let (low, high) = range?;
+            let value = self.value(&terrain.expansion(i, j)?)?;
+
+            // start and stop stay visible, as with the flat colours
+            match terrain.get(i, j)?.ctype {
+                CellType::Start | CellType::Stop => None,
+                _ => Some(gradient(ratio(value, low, high))),
+            }
+        }
+    }
+
+    /// Draws the legend of the range in the top right corner of an area of
+    /// the window
+    pub fn draw_legend(
+        &self,
+        draw: &Draw,
+        range: Option<(f32, f32)>,
+        window: Rect,
+    ) {
+        let Some((low, high)) = range else {
+            return;
+        };
+
+        let bar = Rect::from_w_h(16., 160.)
+            .top_right_of(window)
+            .shift(Vec2::new(-100., -30.));
+        let background = Rect::from_w_h(180., 210.)
+            .top_right_of(window)
+            .shift(Vec2::new(-10., -10.));
+
+        draw.rect()
+            .xy(background.xy())
+            .wh(background.wh())
+            .color(*COLOR_LEGEND);
+        draw.text(self.title())
+            .xy(Vec2::new(background.x(), background.top() - 12.))
+            .w(background.w())
+            .font_size(14)
+            .color(WHITE);
+
+        // lowest at the bottom
+        let step = bar.h() / LEGEND_STEPS as f32;
+        for n in 0..LEGEND_STEPS {
+            let y = bar.bottom() + (n as f32 + 0.5) * step;
+            draw.rect()
+                .x_y(bar.x(), y)
+                .w_h(bar.w(), step + 0.5)
+                .color(gradient(n as f32 / (LEGEND_STEPS - 1) as f32));
+        }
+
+        for (value, y) in [(high, bar.top()), (low, bar.bottom())] {
+            let label = match self {
+                Heatmap::Order => format!("{}", value as usize),
+                _ => format!("{:.1}", value),
+            };
+            draw.text(&label)
+                .x_y(bar.right() + 40., y)
+                .w(60.)
+                .left_justify()
+                .font_size(12)
+                .color(WHITE);
+        }
+    }
+}
+
+/// Where a value lies between the lowest and highest ones
+fn ratio(value: f32, low: f32, high: f32) -> f32 {
+    if high > low {
+        (value - low) / (high - low)
+    } else {
+        0.
+    }
+}
+
+/// Colour of a ratio between 0 and 1, blended between the heat steps
+fn gradient(ratio: f32) -> Rgb8 {
+    let scaled = ratio.clamp(0., 1.) * (COLOR_HEAT.len() - 1) as f32;
+    let index = (scaled as usize).min(COLOR_HEAT.len() - 2);
+    let t = scaled - index as f32;
+    let (a, b) = (COLOR_HEAT[index], COLOR_HEAT[index + 1]);
+    let blend = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+
+    Rgb8::new(
+        blend(a.red, b.red),
+        blend(a.green, b.green),
+        blend(a.blue, b.blue),
+    )
+}
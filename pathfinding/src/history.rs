@@ -0,0 +1,64 @@
+/// Steps of the current search, kept so that it can be rewound
+use crate::board::{SearchChanges, Terrain};
+use crate::pathfinder::StepResult;
+use crate::stats::Stats;
+
+#[derive(Default)]
+pub struct History {
+    /// Changes of each step, with the stats right after it
+    steps: Vec<(SearchChanges, Stats)>,
+    /// Steps currently applied, fewer than recorded once rewound
+    position: usize,
+}
+
+impl History {
+    pub fn clear(&mut self) {
+        self.steps.clear();
+        self.position = 0;
+    }
+
+    /// Adds the step the search just made, which comes after every
+    /// recorded one
+    pub fn push(&mut self, changes: SearchChanges, stats: Stats) {
+        self.steps.push((changes, stats));
+        self.position = self.steps.len();
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Stats after the last applied step, None before the first one
+    pub fn stats(&self) -> Option<&Stats> {
+        self.position.checked_sub(1).map(|n| &self.steps[n].1)
+    }
+
+    /// Undoes the last applied step, returns false at the first one
+    pub fn back(&mut self, terrain: &mut Terrain) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+
+        self.position -= 1;
+        terrain.undo_changes(&self.steps[self.position].0);
+        true
+    }
+
+    /// Applies the next undone step again and tells how it ended, None
+    /// when there is no undone step and the search has to go on
+    pub fn forward(&mut self, terrain: &mut Terrain) -> Option<StepResult> {
+        let (changes, stats) = self.steps.get(self.position)?;
+
+        terrain.redo_changes(changes);
+        self.position += 1;
+        Some(stats.result)
+    }
+}
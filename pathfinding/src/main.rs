@@ -1,35 +1,142 @@
 #![allow(dead_code)]
 /// Visual pathfinding
+mod agent;
+mod bench;
 mod board;
+mod camera;
+mod comparison;
+mod crowd;
+mod generator;
 mod handler;
+mod heatmap;
+mod history;
+mod mapfile;
+mod pathfinder;
+mod pngfile;
+mod stats;
+mod tool;
+mod undo;
 
-use board::{CellType, Terrain};
+use agent::{Agent, Team};
+use board::{CellType, SearchState, Terrain};
+use camera::Camera;
+use comparison::Comparison;
+use crowd::Crowd;
+use generator::cave::CaveRules;
+use generator::landscape::NoiseKind;
+use generator::{Generator, MazeAlgorithm};
 use handler::*;
+use heatmap::Heatmap;
+use history::History;
 use nannou::prelude::*;
 use nannou::winit::event;
-use std::collections::HashSet;
-use std::time::Duration;
+use pathfinder::{
+    cbs, route, AStarSettings, Algorithm, GoalMode, Pathfinder, StepResult,
+};
+use stats::Stats;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tool::Editor;
+use undo::UndoStack;
 
-const PAUSE_DURATION: Duration = Duration::from_millis(50);
+/// Time between two ticks at each speed, the fastest one runs several
+/// search steps per frame
+const TICKS: [Duration; 6] = [
+    Duration::ZERO,
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(200),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+];
+const DEFAULT_SPEED: usize = 2;
+/// Search or maze steps per frame at the fastest speed
+const INSTANT_STEPS: usize = 200;
+const MAP_FILE: &str = "pathfinding.map";
 
 #[derive(PartialEq)]
 enum State {
     Building,
     Initializing,
     Running,
+    Generating,
+    Walking,
+    /// Agents of a multi-agent plan moving together
+    Moving,
+    /// Particles following the flow field
+    Flowing,
 }
 
 pub struct Model {
     terrain: Terrain,
     next_tick: Duration,
+    /// Index in TICKS
+    speed: usize,
+    paused: bool,
     state: State,
     selected_cell: CellType,
-    seen: HashSet<(usize, usize)>,
-    current: Vec<(usize, usize)>,
-    next: Vec<(usize, usize)>,
+    editor: Editor,
+    algorithm: Algorithm,
+    astar: AStarSettings,
+    pathfinder: Box<dyn Pathfinder>,
+    path: Vec<(usize, usize)>,
+    map_file: PathBuf,
+    /// Cells of the maps read from images, one per pixel when None
+    import_size: Option<(usize, usize)>,
+    maze: MazeAlgorithm,
+    seed: u64,
+    generator: Option<Box<dyn Generator>>,
+    /// Edits made while a path is shown are handed to the search
+    replanning: bool,
+    /// Whether the pathfinder holds a search of the current board
+    searched: bool,
+    goal_mode: GoalMode,
+    /// Stops of the route in visiting order, when visiting them all
+    route: Vec<(usize, usize)>,
+    agent: Option<Agent>,
+    /// Cells painted while the agent walks, handed to its next replanning
+    edits: Vec<(usize, usize)>,
+    team: Option<Team>,
+    crowd: Option<Crowd>,
+    /// Colouring of the expanded cells, flat when off
+    heatmap: Heatmap,
+    /// Steps of the current search, to step backward
+    history: History,
+    stats: Option<Stats>,
+    /// Viewports solving the board with several algorithms
+    comparison: Option<Comparison>,
+    camera: Camera,
+    /// Edits of the map, to undo them
+    undo: UndoStack,
+    /// Last mouse position while panning
+    drag: Option<Vec2>,
+}
+
+impl Model {
+    /// Cells can be painted while building, while the agent walks or the
+    /// crowd flows, or during a live replanning, comparisons only get
+    /// edited while building
+    pub fn can_edit(&self) -> bool {
+        self.state == State::Building
+            || self.comparison.is_none()
+                && (self.state == State::Walking
+                    || self.state == State::Flowing
+                    || self.replanning && self.state == State::Running)
+    }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // headless mode, no window gets opened
+    if args.get(1).is_some_and(|arg| arg == "bench") {
+        if let Err(e) = bench::run(&args[2..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     nannou::app(model)
         .update(update)
         .event(event)
@@ -41,25 +148,93 @@ fn model(app: &App) -> Model {
     app.set_loop_mode(LoopMode::rate_fps(0.2));
     let window_rect = app.window_rect();
 
+    // pathfinding [file.map] [--seed N] [--size WxH]
+    let mut map_file = PathBuf::from(MAP_FILE);
+    let mut seed = 0;
+    let mut terrain = Terrain::new(window_rect.w(), window_rect.h());
+    let mut import_size = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                seed = args.next().and_then(|s| s.parse().ok()).unwrap_or(0)
+            }
+            "--size" => match args.next().as_deref().and_then(parse_size) {
+                Some((w, h)) => {
+                    import_size = Some((w, h));
+                    terrain = Terrain::with_size(w, h, 1.);
+                    terrain.fit(window_rect.w(), window_rect.h());
+                    println!("Board has {} tiles ({}x{})", w * h, w, h);
+                }
+                None => println!("Expected a size like 200x100"),
+            },
+            _ => map_file = PathBuf::from(arg),
+        }
+    }
+
     Model {
-        terrain: Terrain::new(window_rect.w(), window_rect.h()),
+        terrain,
         next_tick: Duration::from_millis(500),
+        speed: DEFAULT_SPEED,
+        paused: false,
         state: State::Building,
         selected_cell: CellType::Wall,
-        seen: HashSet::new(),
-        current: Vec::new(),
-        next: Vec::new(),
+        editor: Editor::default(),
+        algorithm: Algorithm::BreadthFirst,
+        astar: AStarSettings::default(),
+        pathfinder: Algorithm::BreadthFirst.build(&AStarSettings::default()),
+        path: Vec::new(),
+        map_file,
+        import_size,
+        maze: MazeAlgorithm::Backtracker,
+        seed,
+        generator: None,
+        replanning: false,
+        searched: false,
+        goal_mode: GoalMode::Nearest,
+        route: Vec::new(),
+        agent: None,
+        edits: Vec::new(),
+        team: None,
+        crowd: None,
+        heatmap: Heatmap::Off,
+        history: History::default(),
+        stats: None,
+        comparison: None,
+        camera: Camera::default(),
+        undo: UndoStack::default(),
+        drag: None,
     }
 }
 
+/// Width and height in cells, as in 200x100
+fn parse_size(size: &str) -> Option<(usize, usize)> {
+    let (w, h) = size.split_once('x')?;
+    let (w, h) = (w.parse().ok()?, h.parse().ok()?);
+
+    (w > 0 && h > 0).then_some((w, h))
+}
+
 fn event(app: &App, model: &mut Model, event: Event) {
-    if let Event::DeviceEvent(_id, event) = event {
+    if let Event::WindowEvent {
+        simple: Some(WindowEvent::KeyPressed(key)),
+        ..
+    } = event
+    {
+        handle_key(app, model, key);
+    } else if let Event::WindowEvent {
+        simple: Some(WindowEvent::DroppedFile(path)),
+        ..
+    } = event
+    {
+        handle_drop(app, model, &path);
+    } else if let Event::DeviceEvent(_id, event) = event {
         match event {
             event::DeviceEvent::Button { button, state } => {
                 handle_click(app, model, button, state);
             }
             event::DeviceEvent::MouseWheel { delta } => {
-                handle_wheel(model, delta);
+                handle_wheel(app, model, delta);
             }
             _ => (),
         }
@@ -67,83 +242,641 @@ fn event(app: &App, model: &mut Model, event: Event) {
 }
 
 fn update(app: &App, model: &mut Model, update: Update) {
-    // Fluid cell placement
-    if State::Building == model.state
-        && app
-            .mouse
-            .buttons
-            .pressed()
-            .any(|(button, _)| button == MouseButton::Left)
-    {
-        model
-            .terrain
-            .place(app.mouse.position(), model.selected_cell);
+    let dragging = app
+        .mouse
+        .buttons
+        .pressed()
+        .any(|(button, _)| button == MouseButton::Left);
+
+    // shift and drag pans the board instead of painting it
+    let panning =
+        dragging && app.keys.mods.shift() && model.comparison.is_none();
+    match model.drag {
+        Some(last) if panning => {
+            model.camera.pan += app.mouse.position() - last;
+            model.drag = Some(app.mouse.position());
+        }
+        _ => model.drag = panning.then(|| app.mouse.position()),
+    }
+
+    // cells go on the board with the editing tool, the brush as the mouse
+    // moves, shapes once the button is released
+    if model.can_edit() {
+        let position = app.mouse.position();
+        let cell = match &model.comparison {
+            Some(comparison) => comparison.cell_at(position),
+            None => model.terrain.cell_under(&model.camera, position),
+        };
+        // a brush of starts would leave only the last one
+        let single = model.selected_cell == CellType::Start
+            && model.terrain.single_start;
+        let cells = model.editor.update(
+            &model.terrain,
+            dragging && !panning,
+            cell,
+            single,
+        );
+        paint(model, &cells);
+
+        // the whole drag is undone at once
+        if !model.editor.is_dragging() {
+            model.undo.end(&model.terrain);
+        }
+    } else if model.editor.is_dragging() {
+        model.editor.cancel();
+        model.undo.end(&model.terrain);
+    }
+
+    // particles move in real time, not tick by tick
+    if let Some(crowd) = model.crowd.as_mut().filter(|_| !model.paused) {
+        let elapsed = update.since_last.as_secs_f32().min(0.1);
+        crowd.update(&model.terrain, elapsed);
     }
 
-    if model.next_tick > update.since_start {
+    // a search started while paused is set up, then waits for a step
+    if model.paused && model.state != State::Initializing
+        || model.next_tick > update.since_start
+    {
         return;
     }
-    model.next_tick += PAUSE_DURATION;
+    model.next_tick = update.since_start + TICKS[model.speed];
+
+    let steps = match model.speed {
+        0 => INSTANT_STEPS,
+        _ => 1,
+    };
+    advance(model, steps);
+}
+
+/// Goes a number of steps further in a search or a maze, agents only ever
+/// move one cell at a time so that they can be followed
+pub fn advance(model: &mut Model, steps: usize) {
+    let generating = model.state == State::Generating;
+
+    tick(model);
+    for _ in 1..steps {
+        if !matches!(model.state, State::Running | State::Generating) {
+            break;
+        }
+        tick(model);
+    }
 
+    // the boards compared follow the maze once per frame
+    if generating {
+        if let Some(comparison) = model.comparison.as_mut() {
+            comparison.sync(&model.terrain);
+        }
+    }
+}
+
+/// Moves whatever is going on one step further
+fn tick(model: &mut Model) {
     match model.state {
+        State::Initializing if model.comparison.is_some() => {
+            start_comparison(model)
+        }
+        State::Running if model.comparison.is_some() => {
+            let Some(comparison) = model.comparison.as_mut() else {
+                return;
+            };
+
+            if !comparison.step() {
+                println!("Every search is over");
+                model.state = State::Building;
+            }
+        }
+
+        State::Initializing if !model.terrain.single_start => plan_team(model),
+
         State::Initializing => {
-            println!("Starting pathfinding");
+            // the agent searches from where it stands, for the stops it
+            // did not visit yet
+            let (start, goals) = match &model.agent {
+                Some(agent) if model.goal_mode == GoalMode::VisitAll => {
+                    (Some(agent.position()), model.route.clone())
+                }
+                Some(agent) => {
+                    (Some(agent.position()), model.terrain.stops.clone())
+                }
+                None => (model.terrain.start(), model.terrain.stops.clone()),
+            };
+            let Some(start) = start.filter(|_| !goals.is_empty()) else {
+                println!("Place a start and a stop before searching");
+                model.searched = false;
+                model.state = State::Building;
+                return;
+            };
+
+            reset_search(model);
+            if model.goal_mode == GoalMode::VisitAll {
+                plan_route(model, start, &goals);
+                return;
+            }
 
-            let start_pos = model.terrain.start_stop[0].unwrap();
-            model.seen.insert(start_pos);
-            model.current.push(start_pos);
+            model.pathfinder = model.algorithm.build(&model.astar);
+            model.pathfinder.init(&model.terrain, start, &goals);
+            model.stats = Some(Stats::new(model.pathfinder.as_ref()));
+            println!("Starting pathfinding ({})", model.pathfinder.name());
+            model.searched = true;
 
             model.state = State::Running;
         }
 
         State::Running => {
-            // get next cell if any
-            if model.current.is_empty() {
-                if model.next.is_empty() {
-                    println!("Search is over, there is no path");
-                    model.seen.drain();
-                    model.state = State::Building;
-                } else {
-                    println!("Current is now empty");
-                    model.current = model.next.drain(..).collect();
-                }
+            // steps undone earlier are replayed before searching further
+            if let Some(result) = model.history.forward(&mut model.terrain) {
+                model.stats = model.history.stats().cloned();
+                end_step(model, result);
                 return;
             }
 
-            // get all neighbours
-            let (i, j) = model.current.pop().unwrap();
-            let neighbours = model.terrain.get_neighbours(i, j);
+            model.terrain.record();
+            let started = Instant::now();
+            let result = model.pathfinder.step(&mut model.terrain);
+            let elapsed = started.elapsed();
 
-            for (k, l) in neighbours {
-                if model.seen.contains(&(k, l)) {
-                    continue;
-                }
+            let pathfinder = model.pathfinder.as_ref();
+            model
+                .stats
+                .get_or_insert_with(|| Stats::new(pathfinder))
+                .update(pathfinder, &model.terrain, elapsed, result);
+            end_step(model, result);
 
-                let mut neighbour_cell = model.terrain.get_mut(k, l).unwrap();
-                match neighbour_cell.ctype {
-                    CellType::Empty => {
-                        neighbour_cell.ctype = CellType::Start;
-                        model.seen.insert((k, l));
-                        model.next.push((k, l));
-                    }
-                    CellType::Stop => {
-                        println!("Search is over, found exit");
-                        model.seen.drain();
-                        model.state = State::Building;
-                        break;
-                    }
-                    _ => (),
-                }
+            let changes = model.terrain.take_changes();
+            if let Some(stats) = model.stats.clone() {
+                model.history.push(changes, stats);
+            }
+        }
+
+        State::Walking => {
+            let Some(agent) = model.agent.as_mut() else {
+                model.state = State::Building;
+                return;
+            };
+
+            if agent.is_blocked(&model.terrain) {
+                let position = agent.position();
+                println!("Path is blocked, replanning from {:?}", position);
+                replan_agent(model, position);
+            } else if agent.has_arrived() {
+                println!("Agent reached the stop");
+                model.agent = None;
+                model.state = State::Building;
+            } else {
+                agent.advance();
+                let position = agent.position();
+                model.route.retain(|&stop| stop != position);
+            }
+        }
+
+        State::Moving => {
+            let Some(team) = model.team.as_mut() else {
+                model.state = State::Building;
+                return;
+            };
+
+            if !team.advance() {
+                println!("Every agent arrived");
+                model.state = State::Building;
+            }
+        }
+
+        State::Generating => {
+            let Some(generator) = model.generator.as_mut() else {
+                model.state = State::Building;
+                return;
+            };
+
+            if !generator.step(&mut model.terrain) {
+                generator::place_start_stop(&mut model.terrain);
+                model.undo.end(&model.terrain);
+                println!("Maze is done");
+                model.generator = None;
+                model.state = State::Building;
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Ends the search once a step found the path or ran out of cells
+fn end_step(model: &mut Model, result: StepResult) {
+    match result {
+        StepResult::Running => (),
+        StepResult::Found => {
+            model.path = model.pathfinder.path().unwrap_or_default();
+            for &(i, j) in &model.path {
+                model.terrain.set_search_state(i, j, SearchState::OnPath);
+            }
+            println!(
+                "Search is over, found exit ({} cells long, costs {})",
+                model.path.len(),
+                model.terrain.path_cost(&model.path)
+            );
+            if let Some(stats) = model.stats.as_mut() {
+                stats.found(&model.terrain, &model.path);
+                println!("{}", stats.log_line());
+            }
+            model.state = State::Building;
+
+            if let Some(agent) = model.agent.as_mut() {
+                agent.follow(model.path.clone());
+                model.state = State::Walking;
+            }
+        }
+        StepResult::NoPath => {
+            println!("Search is over, there is no path");
+            if let Some(stats) = &model.stats {
+                println!("{}", stats.log_line());
+            }
+            model.agent = None;
+            model.state = State::Building;
+        }
+    }
+}
+
+/// Starts generating the selected maze, it then grows on every tick
+pub fn start_generating(model: &mut Model) {
+    let mut generator = model.maze.build(model.seed);
+    println!("Generating {} (seed {})", generator.name(), model.seed);
+
+    reset_search(model);
+    model.undo.begin(&model.terrain);
+    generator.init(&mut model.terrain);
+    model.generator = Some(generator);
+    model.state = State::Generating;
+}
+
+/// Replaces the board with a cave or a landscape, keeping its main region
+pub fn generate_map(model: &mut Model, landscape: Option<NoiseKind>) {
+    reset_search(model);
+    model.undo.begin(&model.terrain);
+
+    match landscape {
+        None => {
+            println!("Generating a cave (seed {})", model.seed);
+            generator::cave::generate(
+                &mut model.terrain,
+                model.seed,
+                &CaveRules::default(),
+            );
+        }
+        Some(kind) => {
+            println!("Generating a {:?} landscape (seed {})", kind, model.seed);
+            generator::landscape::generate(
+                &mut model.terrain,
+                model.seed,
+                kind,
+            );
+        }
+    }
+
+    generator::keep_largest_region(&mut model.terrain);
+    model.undo.end(&model.terrain);
+}
+
+/// Orders the stops and shows the whole route at once, the legs come from
+/// Dijkstra explorations rather than the selected algorithm
+fn plan_route(
+    model: &mut Model,
+    start: (usize, usize),
+    stops: &[(usize, usize)],
+) {
+    let route = route::plan(&model.terrain, start, stops);
+    if !route.unreachable.is_empty() {
+        println!("Stops {:?} can't be reached", route.unreachable);
+    }
+
+    model.path = route.path;
+    model.route = route.stops;
+    for &(i, j) in &model.path {
+        model.terrain.set_search_state(i, j, SearchState::OnPath);
+    }
+    println!(
+        "Route through {} stops costs {} ({})",
+        model.route.len(),
+        route.cost,
+        if route.exact {
+            "best order"
+        } else {
+            "2-opt order"
+        }
+    );
+    model.searched = true;
+    model.state = State::Building;
+
+    if let Some(agent) = model.agent.as_mut() {
+        agent.follow(model.path.clone());
+        model.state = State::Walking;
+    }
+}
+
+/// Shows one viewport, or splits the window to compare algorithms
+pub fn compare(model: &mut Model, count: usize, window: Rect) {
+    reset_search(model);
+    model.agent = None;
+
+    model.comparison = match count {
+        1 => None,
+        _ => Some(Comparison::new(count, &model.terrain, window)),
+    };
+    println!("{} viewports", count);
+}
+
+/// Starts the search of every viewport from the same start
+fn start_comparison(model: &mut Model) {
+    model.state = State::Building;
+    let Some(comparison) = model.comparison.as_mut() else {
+        return;
+    };
+
+    if !comparison.start(&model.astar) {
+        println!("Place a start and a stop before searching");
+        return;
+    }
+    println!("Comparing algorithms in lockstep");
+    model.state = State::Running;
+}
+
+/// Plans a path for every start and stop pair, then moves all the agents
+fn plan_team(model: &mut Model) {
+    reset_search(model);
+    model.state = State::Building;
+
+    let starts = &model.terrain.starts;
+    let stops = &model.terrain.stops;
+    if starts.len() != stops.len() {
+        println!(
+            "{} starts and {} stops, the extra ones are left out",
+            starts.len(),
+            stops.len()
+        );
+    }
+    let pairs: Vec<_> =
+        starts.iter().copied().zip(stops.iter().copied()).collect();
+    if pairs.is_empty() {
+        println!("Place a start and a stop for each agent before planning");
+        return;
+    }
+
+    let plan = cbs::plan(&model.terrain, &pairs);
+    println!(
+        "Planned {} agents with {:?}, {} ticks, {} conflicts left",
+        pairs.len(),
+        plan.method,
+        plan.duration(),
+        plan.conflicts.len()
+    );
+    for conflict in &plan.conflicts {
+        println!("  {:?}", conflict);
+    }
+
+    model.team = Some(Team::new(plan));
+    model.state = State::Moving;
+}
+
+/// Sends a crowd down the flow field to the stops, or stops it
+pub fn toggle_flowing(model: &mut Model) {
+    if model.state == State::Flowing {
+        model.crowd = None;
+        model.state = State::Building;
+        return;
+    }
+    if model.terrain.stops.is_empty() {
+        println!("Place a stop before computing a flow field");
+        return;
+    }
+
+    reset_search(model);
+    model.crowd = Some(Crowd::new(&model.terrain, model.seed));
+    println!("Crowd is following the flow field");
+    model.state = State::Flowing;
+}
+
+/// Undoes the last step of the search, which then waits for a step
+/// forward, a finished search goes back to running
+pub fn step_back(model: &mut Model) {
+    model.paused = true;
+
+    let searching = matches!(model.state, State::Running | State::Building);
+    if !searching || !model.history.back(&mut model.terrain) {
+        println!("No search step to go back to");
+        return;
+    }
+    model.path.clear();
+    model.state = State::Running;
+    model.stats = match model.history.stats() {
+        Some(stats) => Some(stats.clone()),
+        None => Some(Stats::new(model.pathfinder.as_ref())),
+    };
+    println!(
+        "Back to step {} of {}",
+        model.history.position(),
+        model.history.len()
+    );
+}
+
+/// Clears the search overlay and everything the last search left behind
+pub fn reset_search(model: &mut Model) {
+    model.history.clear();
+    model.stats = None;
+    model.team = None;
+    model.crowd = None;
+    model.terrain.reset_search();
+    model.path.clear();
+    model.route.clear();
+    model.searched = false;
+}
+
+/// Replaces the board with a .map file or an image, which can be undone
+pub fn load_file(app: &App, model: &mut Model, path: &Path) {
+    let loaded = if pngfile::is_png(path) {
+        let single_start = model.terrain.single_start;
+        Terrain::load_png(path, model.import_size, single_start)
+    } else {
+        Terrain::load_map(path)
+    };
+    let mut terrain = match loaded {
+        Ok(terrain) => terrain,
+        Err(e) => {
+            println!("Could not load map: {}", e);
+            return;
+        }
+    };
+
+    let window_rect = app.window_rect();
+    terrain.fit(window_rect.w(), window_rect.h());
+    terrain.connectivity = model.terrain.connectivity;
+    terrain.corner_cutting = model.terrain.corner_cutting;
+    terrain.single_start = model.terrain.single_start;
+
+    let before = std::mem::replace(&mut model.terrain, terrain);
+    model.undo.push_load(before, &model.terrain);
+    model.camera = Camera::default();
+    reset_search(model);
+    println!("Loaded map from {}", path.display());
+}
+
+/// Writes the board to a .map file or an image, by the extension
+pub fn save_file(model: &Model, path: &Path) {
+    let saved = if pngfile::is_png(path) {
+        model.terrain.save_png(path)
+    } else {
+        model.terrain.save_map(path)
+    };
+
+    match saved {
+        Ok(()) => println!("Saved map to {}", path.display()),
+        Err(e) => println!("Could not save map: {}", e),
+    }
+}
+
+/// Sends an agent along the found path, from the start
+pub fn start_walking(model: &mut Model) {
+    if model.path.is_empty() {
+        println!("Find a path before walking it");
+        return;
+    }
+
+    model.agent = Some(Agent::new(model.path.clone()));
+    model.edits.clear();
+    model.state = State::Walking;
+}
+
+/// Sets cells to the selected type, then hands them to whatever runs on
+/// the board
+fn paint(model: &mut Model, cells: &[(usize, usize)]) {
+    if cells.is_empty() {
+        return;
+    }
+    model.undo.begin(&model.terrain);
+
+    let endpoints = (model.terrain.start(), model.terrain.stops.len());
+    let placed: Vec<(usize, usize)> = cells
+        .iter()
+        .filter_map(|&(i, j)| {
+            model.terrain.place_cell(i, j, model.selected_cell)
+        })
+        .collect();
+    if placed.is_empty() {
+        return;
+    }
+
+    // every viewport gets the edit
+    if let Some(comparison) = model.comparison.as_mut() {
+        comparison.sync(&model.terrain);
+        return;
+    }
+    let moved = endpoints != (model.terrain.start(), model.terrain.stops.len());
+    match model.state {
+        State::Walking if moved => {
+            println!("The start or stop moved, the agent stops");
+            model.agent = None;
+            model.state = State::Building;
+        }
+        State::Walking => model.edits.extend(placed),
+        State::Flowing => {
+            if let Some(crowd) = model.crowd.as_mut() {
+                crowd.update_field(&model.terrain);
             }
         }
+        _ if model.replanning => replan(model, &placed, moved),
         _ => (),
     }
 }
 
+/// Hands edited cells to the last search, which either repairs its path
+/// or starts over when it can't, or when the start or stop moved
+pub fn replan(model: &mut Model, cells: &[(usize, usize)], moved: bool) {
+    if !model.searched {
+        return;
+    }
+    clear_path(model);
+    // the repaired search can't be rewound past the edit
+    model.history.clear();
+
+    // routes are planned again from scratch
+    if moved
+        || model.goal_mode == GoalMode::VisitAll
+        || !model.pathfinder.repair(&mut model.terrain, cells)
+    {
+        model.state = State::Initializing;
+    } else {
+        model.state = State::Running;
+    }
+}
+
+/// Searches again from the agent, with the cells painted while it walked
+fn replan_agent(model: &mut Model, position: (usize, usize)) {
+    let edits = std::mem::take(&mut model.edits);
+    clear_path(model);
+    model.history.clear();
+
+    if model.searched
+        && model.goal_mode == GoalMode::Nearest
+        && model.pathfinder.move_start(position)
+        && model.pathfinder.repair(&mut model.terrain, &edits)
+    {
+        model.state = State::Running;
+    } else {
+        model.state = State::Initializing;
+    }
+}
+
+/// Removes the path, its cells stay marked as visited
+fn clear_path(model: &mut Model) {
+    for &(i, j) in &model.path {
+        model.terrain.set_search_state(i, j, SearchState::Closed);
+    }
+    model.path.clear();
+}
+
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
 
-    model.terrain.draw(&draw);
+    if let Some(comparison) = &model.comparison {
+        comparison.draw(
+            app,
+            &draw,
+            model.heatmap,
+            &model.editor,
+            model.selected_cell,
+        );
+        draw.to_frame(app, &frame).expect("Could not draw to frame");
+        return;
+    }
+
+    // the board and what moves on it go through the camera, the legend
+    // and the stats stay in place
+    let world = model.camera.transform(&draw);
+    let zoom = model.camera.zoom;
+    let range = model.terrain.draw(app, &world, model.heatmap);
+    if model.terrain.cell_size() * zoom > 4. {
+        let visible = model.camera.visible(app.window_rect());
+        model.terrain.draw_grid(&world, visible, 1. / zoom);
+    }
+    match &model.agent {
+        Some(agent) if model.state == State::Walking => {
+            model.terrain.draw_path(&world, agent.remaining());
+            agent.draw(&world, &model.terrain);
+        }
+        Some(agent) => agent.draw(&world, &model.terrain),
+        None => model.terrain.draw_path(&world, &model.path),
+    }
+    model.terrain.draw_waypoints(&world, &model.route);
+    model
+        .editor
+        .draw(&world, &model.terrain, model.selected_cell);
+    if let Some(team) = &model.team {
+        team.draw(&world, &model.terrain);
+    }
+    if let Some(crowd) = &model.crowd {
+        crowd.draw(&world, &model.terrain);
+    }
+    model.heatmap.draw_legend(&draw, range, app.window_rect());
+    if let Some(stats) = &model.stats {
+        stats.draw(&draw, app.window_rect());
+    }
 
     // Write to the window frame
     draw.to_frame(app, &frame).expect("Could not draw to frame");
@@ -0,0 +1,157 @@
+/// Import and export of MovingAI .map files
+/// (https://movingai.com/benchmarks/formats.html)
+use crate::board::{CellType, Terrain};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+impl CellType {
+    /// Trees and out of bounds cells cannot be crossed, they become walls.
+    /// Swamps become mud.
+    pub fn from_map_char(c: char) -> Option<CellType> {
+        match c {
+            '.' | 'G' => Some(CellType::Empty),
+            '@' | 'O' | 'T' => Some(CellType::Wall),
+            'S' => Some(CellType::Mud),
+            'W' => Some(CellType::Water),
+            _ => None,
+        }
+    }
+
+    /// The format has no grass nor sand, they are saved as passable cells
+    pub fn map_char(&self) -> char {
+        match self {
+            CellType::Wall => '@',
+            CellType::Mud => 'S',
+            CellType::Water => 'W',
+            CellType::Empty
+            | CellType::Start
+            | CellType::Stop
+            | CellType::Grass
+            | CellType::Sand => '.',
+        }
+    }
+}
+
+impl Terrain {
+    pub fn load_map(path: &Path) -> io::Result<Terrain> {
+        let text = fs::read_to_string(path)?;
+
+        Terrain::parse_map(&text)
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+    }
+
+    pub fn save_map(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_map_string())
+    }
+
+    /// Reads a map, the first row of the file is the top of the board
+    pub fn parse_map(text: &str) -> Result<Terrain, String> {
+        let mut lines = text.lines();
+        let mut w = None;
+        let mut h = None;
+
+        for line in lines.by_ref() {
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("type"), _) => (),
+                (Some("height"), Some(value)) => h = value.parse().ok(),
+                (Some("width"), Some(value)) => w = value.parse().ok(),
+                (Some("map"), None) => break,
+                (None, _) => (),
+                _ => return Err(format!("Unexpected header line `{}`", line)),
+            }
+        }
+
+        let (Some(w), Some(h)) = (w, h) else {
+            return Err("Missing width or height".to_string());
+        };
+
+        let mut terrain = Terrain::with_size(w, h, 1.);
+
+        for row in 0..h {
+            let line = lines
+                .next()
+                .ok_or_else(|| format!("Expected {} rows, got {}", h, row))?;
+            let chars: Vec<char> = line.trim_end().chars().collect();
+
+            if chars.len() != w {
+                return Err(format!(
+                    "Row {} has {} cells instead of {}",
+                    row,
+                    chars.len(),
+                    w
+                ));
+            }
+
+            for (i, c) in chars.into_iter().enumerate() {
+                let ctype = CellType::from_map_char(c).ok_or_else(|| {
+                    format!("Unknown cell `{}` at row {}", c, row)
+                })?;
+                terrain.get_mut(i, h - 1 - row).unwrap().ctype = ctype;
+            }
+        }
+
+        Ok(terrain)
+    }
+
+    pub fn to_map_string(&self) -> String {
+        let (w, h) = (self.width(), self.height());
+        let mut text = format!("type octile\nheight {}\nwidth {}\nmap\n", h, w);
+
+        for j in (0..h).rev() {
+            for i in 0..w {
+                text.push(self.get(i, j).unwrap().ctype.map_char());
+            }
+            writeln!(text).unwrap();
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str =
+        "type octile\nheight 3\nwidth 5\nmap\n.@@SW\n..T.G\nO...@\n";
+
+    #[test]
+    fn map_round_trip() {
+        let terrain = Terrain::parse_map(MAP).unwrap();
+        assert_eq!((terrain.width(), terrain.height()), (5, 3));
+        // the first row is the top of the board
+        assert_eq!(terrain.get(1, 2).unwrap().ctype, CellType::Wall);
+        assert_eq!(terrain.get(3, 2).unwrap().ctype, CellType::Mud);
+        assert_eq!(terrain.get(0, 0).unwrap().ctype, CellType::Wall);
+
+        let text = terrain.to_map_string();
+        assert_eq!(
+            text,
+            "type octile\nheight 3\nwidth 5\nmap\n.@@SW\n..@..\n@...@\n"
+        );
+        let again = Terrain::parse_map(&text).unwrap();
+        for i in 0..5 {
+            for j in 0..3 {
+                assert_eq!(
+                    again.get(i, j).unwrap().ctype,
+                    terrain.get(i, j).unwrap().ctype
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_maps_are_refused() {
+        for text in [
+            "type octile\nheight 2\nwidth 2\nmap\n..\n",
+            "type octile\nheight 1\nwidth 2\nmap\n...\n",
+            "type octile\nheight 1\nwidth 2\nmap\n.x\n",
+            "type octile\nwidth 2\nmap\n..\n",
+        ] {
+            assert!(Terrain::parse_map(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+/// A* search, Dijkstra guided towards the goal by a heuristic
+use super::{
+    reconstruct_path, AStarSettings, OpenNode, Pathfinder, StepResult,
+};
+use crate::board::{SearchState, Terrain};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+#[derive(Default)]
+pub struct AStar {
+    settings: AStarSettings,
+    open: BinaryHeap<OpenNode>,
+    closed: HashSet<(usize, usize)>,
+    distances: HashMap<(usize, usize), f32>,
+    parents: HashMap<(usize, usize), (usize, usize)>,
+    goals: Vec<(usize, usize)>,
+    pushed: usize,
+    /// Goal the search got to
+    reached: Option<(usize, usize)>,
+}
+
+impl AStar {
+    pub fn new(settings: AStarSettings) -> AStar {
+        AStar {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    fn push(&mut self, pos: (usize, usize), g: f32) {
+        let h = self.settings.heuristic.nearest(pos, &self.goals);
+
+        self.open.push(OpenNode {
+            cost: g + self.settings.weight * h,
+            tie: self.settings.tie_break.key(g, self.pushed),
+            pos,
+        });
+        self.pushed += 1;
+    }
+}
+
+impl Pathfinder for AStar {
+    fn name(&self) -> String {
+        if self.settings.weight == 1. {
+            format!("A* ({:?})", self.settings.heuristic)
+        } else {
+            format!(
+                "Weighted A* ({:?} x{})",
+                self.settings.heuristic, self.settings.weight
+            )
+        }
+    }
+
+    fn init(
+        &mut self,
+        _terrain: &Terrain,
+        start: (usize, usize),
+        goals: &[(usize, usize)],
+    ) {
+        self.open.clear();
+        self.closed.clear();
+        self.distances = HashMap::from([(start, 0.)]);
+        self.parents.clear();
+        self.goals = goals.to_vec();
+        self.pushed = 0;
+        self.reached = None;
+
+        self.push(start, 0.);
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> StepResult {
+        // skip the outdated copies left in the heap
+        let ((i, j), f) = loop {
+            match self.open.pop() {
+                Some(node) if self.closed.contains(&node.pos) => continue,
+                Some(node) => break (node.pos, node.cost),
+                None => return StepResult::NoPath,
+            }
+        };
+
+        if self.goals.contains(&(i, j)) {
+            self.reached = Some((i, j));
+            return StepResult::Found;
+        }
+
+        self.closed.insert((i, j));
+        terrain.set_search_state(i, j, SearchState::Closed);
+        let g = self.distances[&(i, j)];
+        terrain.expand(i, j, g, f);
+
+        for (k, l) in terrain.get_neighbours(i, j) {
+            if self.closed.contains(&(k, l)) {
+                continue;
+            }
+            let Some(cost) = terrain.move_cost((i, j), (k, l)) else {
+                continue;
+            };
+
+            let distance = g + cost;
+            if self
+                .distances
+                .get(&(k, l))
+                .is_some_and(|&known| known <= distance)
+            {
+                continue;
+            }
+
+            self.distances.insert((k, l), distance);
+            self.parents.insert((k, l), (i, j));
+            self.push((k, l), distance);
+            terrain.set_search_state(k, l, SearchState::Frontier);
+        }
+
+        StepResult::Running
+    }
+
+    fn frontier(&self) -> usize {
+        self.distances.len() - self.closed.len()
+    }
+
+    fn path(&self) -> Option<Vec<(usize, usize)>> {
+        self.reached
+            .map(|goal| reconstruct_path(&self.parents, goal))
+    }
+}
@@ -0,0 +1,300 @@
+/// Bidirectional search, grows a frontier from the start and another one
+/// from the goals until they meet
+use super::{
+    reconstruct_path, AStarSettings, OpenNode, Pathfinder, StepResult,
+};
+use crate::board::{SearchState, Terrain};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// One of the two searches
+#[derive(Default)]
+struct Side {
+    open: BinaryHeap<OpenNode>,
+    closed: HashSet<(usize, usize)>,
+    distances: HashMap<(usize, usize), f32>,
+    parents: HashMap<(usize, usize), (usize, usize)>,
+    /// Where this side is heading, the origins of the other side
+    targets: Vec<(usize, usize)>,
+}
+
+impl Side {
+    /// Smallest cost still in the queue, outdated copies are dropped
+    fn peek(&mut self) -> Option<f32> {
+        while let Some(node) = self.open.peek() {
+            if !self.closed.contains(&node.pos) {
+                return Some(node.cost);
+            }
+            self.open.pop();
+        }
+        None
+    }
+}
+
+/// Breadth first on both sides when there are no settings, A* otherwise
+#[derive(Default)]
+pub struct Bidirectional {
+    settings: Option<AStarSettings>,
+    /// Forward search from the start, backward search from the goals
+    sides: [Side; 2],
+    turn: usize,
+    /// Cost of the best path seen so far, and where both sides meet on it
+    best: Option<(f32, (usize, usize))>,
+    pushed: usize,
+}
+
+impl Bidirectional {
+    pub fn breadth_first() -> Bidirectional {
+        Bidirectional::default()
+    }
+
+    pub fn astar(settings: AStarSettings) -> Bidirectional {
+        Bidirectional {
+            settings: Some(settings),
+            ..Default::default()
+        }
+    }
+
+    /// Cost of a move as seen by a side, the backward side walks moves
+    /// in reverse so it pays for the cell it comes from
+    fn move_cost(
+        &self,
+        terrain: &Terrain,
+        side: usize,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Option<f32> {
+        // either way the cell reached has to be walkable
+        terrain.cost(to.0, to.1)?;
+        let (a, b) = if side == 0 { (from, to) } else { (to, from) };
+
+        match self.settings {
+            // every move counts as one, like breadth first
+            None => Some(1.),
+            Some(_) => terrain.move_cost(a, b),
+        }
+    }
+
+    fn push(&mut self, side: usize, pos: (usize, usize), g: f32) {
+        let (cost, tie) = match self.settings {
+            None => (g, self.pushed as f32),
+            Some(settings) => {
+                let h =
+                    settings.heuristic.nearest(pos, &self.sides[side].targets);
+                (
+                    g + settings.weight * h,
+                    settings.tie_break.key(g, self.pushed),
+                )
+            }
+        };
+
+        self.sides[side].open.push(OpenNode { cost, tie, pos });
+        self.pushed += 1;
+    }
+
+    /// Whether no path can be cheaper than the best one found
+    fn is_done(&mut self) -> bool {
+        let Some((best, _)) = self.best else {
+            return false;
+        };
+        let (Some(forward), Some(backward)) =
+            (self.sides[0].peek(), self.sides[1].peek())
+        else {
+            return true;
+        };
+
+        match self.settings {
+            // both sides are sorted by distance
+            None => forward + backward >= best,
+            // each side is sorted by an estimation of the whole path
+            Some(_) => forward.max(backward) >= best,
+        }
+    }
+}
+
+impl Pathfinder for Bidirectional {
+    fn name(&self) -> String {
+        match self.settings {
+            None => "Bidirectional BFS".to_string(),
+            Some(settings) => {
+                format!("Bidirectional A* ({:?})", settings.heuristic)
+            }
+        }
+    }
+
+    fn init(
+        &mut self,
+        _terrain: &Terrain,
+        start: (usize, usize),
+        goals: &[(usize, usize)],
+    ) {
+        self.sides = [
+            Side {
+                targets: goals.to_vec(),
+                distances: HashMap::from([(start, 0.)]),
+                ..Default::default()
+            },
+            Side {
+                targets: vec![start],
+                distances: goals.iter().map(|&goal| (goal, 0.)).collect(),
+                ..Default::default()
+            },
+        ];
+        self.turn = 0;
+        self.best = goals.contains(&start).then_some((0., start));
+        self.pushed = 0;
+
+        self.push(0, start, 0.);
+        for &goal in goals {
+            self.push(1, goal, 0.);
+        }
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> StepResult {
+        if self.is_done() {
+            return StepResult::Found;
+        }
+
+        // sides take turns, unless one of them has nothing left
+        let side = self.turn;
+        self.turn = 1 - self.turn;
+        let side = match (self.sides[side].peek(), self.sides[1 - side].peek())
+        {
+            (Some(_), _) => side,
+            (None, Some(_)) => 1 - side,
+            (None, None) => return StepResult::NoPath,
+        };
+
+        let (closed_state, frontier_state) = match side {
+            0 => (SearchState::Closed, SearchState::Frontier),
+            _ => (SearchState::ClosedBack, SearchState::FrontierBack),
+        };
+
+        let node = self.sides[side].open.pop().unwrap();
+        let (i, j) = node.pos;
+        self.sides[side].closed.insert((i, j));
+        terrain.set_search_state(i, j, closed_state);
+        let g = self.sides[side].distances[&(i, j)];
+        terrain.expand(i, j, g, node.cost);
+
+        for (k, l) in terrain.get_neighbours(i, j) {
+            if self.sides[side].closed.contains(&(k, l)) {
+                continue;
+            }
+            let Some(cost) = self.move_cost(terrain, side, (i, j), (k, l))
+            else {
+                continue;
+            };
+
+            let distance = g + cost;
+            if self.sides[side]
+                .distances
+                .get(&(k, l))
+                .is_some_and(|&known| known <= distance)
+            {
+                continue;
+            }
+
+            self.sides[side].distances.insert((k, l), distance);
+            self.sides[side].parents.insert((k, l), (i, j));
+            self.push(side, (k, l), distance);
+            terrain.set_search_state(k, l, frontier_state);
+
+            // the other side already reached this cell
+            if let Some(&other) = self.sides[1 - side].distances.get(&(k, l)) {
+                if self.best.is_none_or(|(best, _)| distance + other < best) {
+                    self.best = Some((distance + other, (k, l)));
+                }
+            }
+        }
+
+        StepResult::Running
+    }
+
+    fn frontier(&self) -> usize {
+        self.sides
+            .iter()
+            .map(|side| side.distances.len() - side.closed.len())
+            .sum()
+    }
+
+    fn path(&self) -> Option<Vec<(usize, usize)>> {
+        let (_, meeting) = self.best?;
+
+        let mut path = reconstruct_path(&self.sides[0].parents, meeting);
+        let mut backward = reconstruct_path(&self.sides[1].parents, meeting);
+        backward.reverse();
+        path.extend(backward.into_iter().skip(1));
+
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Connectivity;
+    use crate::pathfinder::tests::{obstacles, solve};
+    use crate::pathfinder::{Algorithm, Dijkstra, Heuristic};
+
+    #[test]
+    fn walls_are_never_searched() {
+        for seed in 0..20 {
+            for algorithm in
+                [Algorithm::BidirectionalBfs, Algorithm::BidirectionalAStar]
+            {
+                let mut terrain = obstacles(12, 9, seed);
+                let mut pathfinder = algorithm.build(&AStarSettings::default());
+                solve(pathfinder.as_mut(), &mut terrain, (0, 0), (11, 8));
+
+                for i in 0..12 {
+                    for j in 0..9 {
+                        if !terrain.is_walkable(i, j) {
+                            assert_eq!(
+                                terrain.search_state(i, j),
+                                Some(SearchState::Unvisited),
+                                "{:?} searched the wall ({}, {})",
+                                algorithm,
+                                i,
+                                j
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_cost_as_dijkstra() {
+        for seed in 0..20 {
+            for connectivity in [Connectivity::Four, Connectivity::Eight] {
+                let mut terrain = obstacles(15, 10, seed);
+                terrain.connectivity = connectivity;
+                let (start, goal) = ((0, 0), (14, 9));
+                let expected =
+                    solve(&mut Dijkstra::default(), &mut terrain, start, goal);
+
+                // octile never overestimates, on 4 or 8 neighbours
+                let mut astar = Bidirectional::astar(AStarSettings {
+                    heuristic: Heuristic::Octile,
+                    ..Default::default()
+                });
+                let cost = solve(&mut astar, &mut terrain, start, goal);
+                match (cost, expected) {
+                    (Some(cost), Some(expected)) => {
+                        assert!((cost - expected).abs() < 1e-3)
+                    }
+                    (cost, expected) => assert_eq!(cost, expected),
+                }
+
+                // breadth first counts moves, which are the cost on 4
+                // neighbours without weights
+                if connectivity == Connectivity::Four {
+                    let mut bfs = Bidirectional::breadth_first();
+                    let cost = solve(&mut bfs, &mut terrain, start, goal);
+                    assert_eq!(cost, expected);
+                }
+            }
+        }
+    }
+}
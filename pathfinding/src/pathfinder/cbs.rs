@@ -0,0 +1,415 @@
+/// Conflict-based search, plans the paths of several agents so that no two
+/// of them share a cell or swap places at the same time.
+/// Every move or wait takes one tick, whatever the terrain costs.
+use crate::board::Terrain;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Two cells, a move or a start with its goal
+type Pair = ((usize, usize), (usize, usize));
+
+/// High level nodes CBS may expand before prioritized planning takes over
+const MAX_NODES: usize = 2000;
+/// Time CBS may take before prioritized planning takes over, the window
+/// doesn't refresh meanwhile
+const TIME_LIMIT: Duration = Duration::from_millis(250);
+
+/// Two agents getting in each other's way
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Conflict {
+    /// Both agents on the same cell at the same time
+    Vertex {
+        agents: (usize, usize),
+        pos: (usize, usize),
+        time: usize,
+    },
+    /// The first agent goes from a cell to another while the second one
+    /// comes the other way, arriving at the given time
+    Edge {
+        agents: (usize, usize),
+        from: (usize, usize),
+        to: (usize, usize),
+        time: usize,
+    },
+}
+
+/// How the paths of a plan were found
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Method {
+    Cbs,
+    /// Agents planned one after the other, avoiding the previous ones
+    Prioritized,
+    /// Agents planned on their own, conflicts remain
+    Independent,
+}
+
+pub struct Plan {
+    /// Cell of each agent at each tick, agents stay on their goal once
+    /// their path ends
+    pub paths: Vec<Vec<(usize, usize)>>,
+    pub conflicts: Vec<Conflict>,
+    pub method: Method,
+}
+
+impl Plan {
+    pub fn position(&self, agent: usize, time: usize) -> (usize, usize) {
+        position(&self.paths[agent], time)
+    }
+
+    /// Ticks until the last agent arrives
+    pub fn duration(&self) -> usize {
+        self.paths
+            .iter()
+            .map(|path| path.len() - 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn position(path: &[(usize, usize)], time: usize) -> (usize, usize) {
+    path[time.min(path.len() - 1)]
+}
+
+/// What one agent is not allowed to do
+#[derive(Clone, Default)]
+struct Constraints {
+    /// Cells it can't be on at a time
+    vertices: HashSet<((usize, usize), usize)>,
+    /// Moves it can't make, arriving at a time
+    edges: HashSet<(Pair, usize)>,
+    /// Cells taken from a time on by agents resting on their goal
+    parked: HashMap<(usize, usize), usize>,
+}
+
+impl Constraints {
+    fn allows(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+        time: usize,
+    ) -> bool {
+        !self.vertices.contains(&(to, time))
+            && !self.edges.contains(&((from, to), time))
+            && self.parked.get(&to).is_none_or(|&since| time < since)
+    }
+
+    /// Whether the agent can stay on a cell for good from a time on
+    fn can_rest(&self, pos: (usize, usize), time: usize) -> bool {
+        !self.parked.contains_key(&pos)
+            && !self
+                .vertices
+                .iter()
+                .any(|&(cell, at)| cell == pos && at >= time)
+    }
+
+    fn horizon(&self) -> usize {
+        self.vertices
+            .iter()
+            .map(|&(_, time)| time)
+            .chain(self.edges.iter().map(|&(_, time)| time))
+            .chain(self.parked.values().copied())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// One agent, with the number of moves from every cell to its goal
+struct Agent {
+    start: (usize, usize),
+    goal: (usize, usize),
+    distances: HashMap<(usize, usize), usize>,
+}
+
+impl Agent {
+    fn new(
+        terrain: &Terrain,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> Agent {
+        let mut distances = HashMap::from([(goal, 0)]);
+        let mut queue = VecDeque::from([goal]);
+
+        while let Some((i, j)) = queue.pop_front() {
+            let distance = distances[&(i, j)];
+            for next in moves(terrain, (i, j)) {
+                distances.entry(next).or_insert_with(|| {
+                    queue.push_back(next);
+                    distance + 1
+                });
+            }
+        }
+
+        Agent {
+            start,
+            goal,
+            distances,
+        }
+    }
+
+    /// A* over cells and ticks, waiting is a move too
+    fn plan(
+        &self,
+        terrain: &Terrain,
+        constraints: &Constraints,
+    ) -> Option<Vec<(usize, usize)>> {
+        // once constraints are over, no path needs more ticks than there
+        // are cells to go through
+        let estimate = |pos| self.distances.get(&pos).copied();
+        let horizon = constraints.horizon() + self.distances.len();
+        if constraints.parked.contains_key(&self.goal) {
+            return None;
+        }
+
+        let mut open =
+            BinaryHeap::from([Reverse((estimate(self.start)?, 0, self.start))]);
+        let mut closed = HashSet::new();
+        let mut parents: HashMap<_, ((usize, usize), usize)> = HashMap::new();
+
+        while let Some(Reverse((_, time, pos))) = open.pop() {
+            if !closed.insert((pos, time)) {
+                continue;
+            }
+
+            if pos == self.goal && constraints.can_rest(pos, time) {
+                let mut path = vec![pos];
+                let mut current = (pos, time);
+                while let Some(&parent) = parents.get(&current) {
+                    path.push(parent.0);
+                    current = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if time >= horizon {
+                continue;
+            }
+
+            for next in moves(terrain, pos).into_iter().chain([pos]) {
+                let Some(distance) = estimate(next) else {
+                    continue;
+                };
+                if !constraints.allows(pos, next, time + 1)
+                    || closed.contains(&(next, time + 1))
+                {
+                    continue;
+                }
+
+                parents.entry((next, time + 1)).or_insert((pos, time));
+                open.push(Reverse((time + 1 + distance, time + 1, next)));
+            }
+        }
+
+        None
+    }
+}
+
+/// Cells an agent can go to in one tick, besides staying
+fn moves(terrain: &Terrain, (i, j): (usize, usize)) -> Vec<(usize, usize)> {
+    terrain
+        .get_neighbours(i, j)
+        .into_iter()
+        .filter(|&(k, l)| terrain.is_walkable(k, l))
+        .collect()
+}
+
+/// Every vertex and edge conflict between the paths
+pub fn find_conflicts(paths: &[Vec<(usize, usize)>]) -> Vec<Conflict> {
+    let duration = paths.iter().map(|path| path.len()).max().unwrap_or(0);
+    let mut conflicts = Vec::new();
+
+    for time in 0..duration {
+        for a in 0..paths.len() {
+            for b in a + 1..paths.len() {
+                let (here_a, here_b) =
+                    (position(&paths[a], time), position(&paths[b], time));
+                if here_a == here_b {
+                    conflicts.push(Conflict::Vertex {
+                        agents: (a, b),
+                        pos: here_a,
+                        time,
+                    });
+                }
+
+                let (next_a, next_b) = (
+                    position(&paths[a], time + 1),
+                    position(&paths[b], time + 1),
+                );
+                if here_a != next_a && here_a == next_b && next_a == here_b {
+                    conflicts.push(Conflict::Edge {
+                        agents: (a, b),
+                        from: here_a,
+                        to: next_a,
+                        time: time + 1,
+                    });
+                }
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// Sum of the arrival times
+fn cost(paths: &[Vec<(usize, usize)>]) -> usize {
+    paths.iter().map(|path| path.len() - 1).sum()
+}
+
+/// Splits on the first conflict, each side forbids it to one of the agents
+fn conflict_based(
+    terrain: &Terrain,
+    agents: &[Agent],
+) -> Option<Vec<Vec<(usize, usize)>>> {
+    let deadline = Instant::now() + TIME_LIMIT;
+    let constraints = vec![Constraints::default(); agents.len()];
+    let paths = agents
+        .iter()
+        .map(|agent| agent.plan(terrain, &constraints[0]))
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut nodes = vec![(constraints, paths)];
+    let mut open = BinaryHeap::from([Reverse((cost(&nodes[0].1), 0))]);
+
+    while let Some(Reverse((_, index))) = open.pop() {
+        if nodes.len() > MAX_NODES || Instant::now() > deadline {
+            return None;
+        }
+
+        let (constraints, paths) = nodes[index].clone();
+        let Some(&conflict) = find_conflicts(&paths).first() else {
+            return Some(paths);
+        };
+
+        let branches = match conflict {
+            Conflict::Vertex {
+                agents: (a, b),
+                pos,
+                time,
+            } => [(a, Some((pos, time)), None), (b, Some((pos, time)), None)],
+            Conflict::Edge {
+                agents: (a, b),
+                from,
+                to,
+                time,
+            } => [
+                (a, None, Some(((from, to), time))),
+                (b, None, Some(((to, from), time))),
+            ],
+        };
+
+        for (agent, vertex, edge) in branches {
+            let mut constraints = constraints.clone();
+            constraints[agent].vertices.extend(vertex);
+            constraints[agent].edges.extend(edge);
+
+            let Some(path) = agents[agent].plan(terrain, &constraints[agent])
+            else {
+                continue;
+            };
+            let mut paths = paths.clone();
+            paths[agent] = path;
+
+            open.push(Reverse((cost(&paths), nodes.len())));
+            nodes.push((constraints, paths));
+        }
+    }
+
+    None
+}
+
+/// Agents plan in turn, each one avoiding the paths already planned
+fn prioritized(
+    terrain: &Terrain,
+    agents: &[Agent],
+) -> Option<Vec<Vec<(usize, usize)>>> {
+    let mut reserved = Constraints::default();
+    let mut paths = Vec::new();
+
+    for agent in agents {
+        let path = agent.plan(terrain, &reserved)?;
+
+        for (time, &pos) in path.iter().enumerate() {
+            reserved.vertices.insert((pos, time));
+            if time > 0 {
+                reserved.edges.insert(((pos, path[time - 1]), time));
+            }
+        }
+        reserved.parked.insert(agent.goal, path.len() - 1);
+        paths.push(path);
+    }
+
+    Some(paths)
+}
+
+/// Plans every start and goal pair, CBS first, then prioritized planning,
+/// then each agent on its own
+pub fn plan(terrain: &Terrain, pairs: &[Pair]) -> Plan {
+    let agents: Vec<Agent> = pairs
+        .iter()
+        .map(|&(start, goal)| Agent::new(terrain, start, goal))
+        .collect();
+
+    let (paths, method) = if let Some(paths) = conflict_based(terrain, &agents)
+    {
+        (paths, Method::Cbs)
+    } else if let Some(paths) = prioritized(terrain, &agents) {
+        (paths, Method::Prioritized)
+    } else {
+        // agents that can't reach their goal stay where they are
+        let alone = Constraints::default();
+        let paths = agents
+            .iter()
+            .map(|agent| {
+                agent.plan(terrain, &alone).unwrap_or(vec![agent.start])
+            })
+            .collect();
+        (paths, Method::Independent)
+    };
+
+    Plan {
+        conflicts: find_conflicts(&paths),
+        paths,
+        method,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::CellType;
+
+    /// Corridor along the middle row, with a pocket above its middle cell
+    fn corridor(w: usize, pocket: bool) -> Terrain {
+        let mut terrain = Terrain::with_size(w, 3, 1.);
+        for i in 0..w {
+            terrain.set(i, 0, CellType::Wall);
+            if !pocket || i != w / 2 {
+                terrain.set(i, 2, CellType::Wall);
+            }
+        }
+        terrain
+    }
+
+    #[test]
+    fn agents_swapping_in_a_corridor_get_out_of_the_way() {
+        let terrain = corridor(7, true);
+        let plan = plan(&terrain, &[((0, 1), (6, 1)), ((6, 1), (0, 1))]);
+
+        assert_eq!(plan.method, Method::Cbs);
+        assert!(plan.conflicts.is_empty());
+        assert!(find_conflicts(&plan.paths).is_empty());
+        assert_eq!(plan.position(0, plan.duration()), (6, 1));
+        assert_eq!(plan.position(1, plan.duration()), (0, 1));
+    }
+
+    #[test]
+    fn impossible_swaps_are_given_up_in_time() {
+        let terrain = corridor(7, false);
+        let started = Instant::now();
+        let plan = plan(&terrain, &[((0, 1), (6, 1)), ((6, 1), (0, 1))]);
+
+        assert_ne!(plan.method, Method::Cbs);
+        assert!(!plan.conflicts.is_empty());
+        assert!(started.elapsed() < TIME_LIMIT * 4);
+    }
+}
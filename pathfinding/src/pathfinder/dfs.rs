@@ -0,0 +1,74 @@
+/// Depth first search, finds a path but rarely a short one
+use super::{expanded_cost, reconstruct_path, Pathfinder, StepResult};
+use crate::board::{SearchState, Terrain};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+pub struct DepthFirst {
+    stack: Vec<(usize, usize)>,
+    seen: HashSet<(usize, usize)>,
+    parents: HashMap<(usize, usize), (usize, usize)>,
+    goals: Vec<(usize, usize)>,
+    /// Goal the search got to
+    reached: Option<(usize, usize)>,
+}
+
+impl Pathfinder for DepthFirst {
+    fn name(&self) -> String {
+        "Depth first".to_string()
+    }
+
+    fn init(
+        &mut self,
+        _terrain: &Terrain,
+        start: (usize, usize),
+        goals: &[(usize, usize)],
+    ) {
+        self.stack = vec![start];
+        self.seen = HashSet::from([start]);
+        self.parents.clear();
+        self.goals = goals.to_vec();
+        self.reached = None;
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> StepResult {
+        let Some((i, j)) = self.stack.pop() else {
+            return StepResult::NoPath;
+        };
+        // only the start can be a goal here, the others are found as
+        // neighbours
+        if self.goals.contains(&(i, j)) {
+            self.reached = Some((i, j));
+            return StepResult::Found;
+        }
+        terrain.set_search_state(i, j, SearchState::Closed);
+        let g = expanded_cost(terrain, self.parents.get(&(i, j)), (i, j));
+        terrain.expand(i, j, g, g);
+
+        for (k, l) in terrain.get_neighbours(i, j) {
+            if !terrain.is_walkable(k, l) || !self.seen.insert((k, l)) {
+                continue;
+            }
+
+            self.parents.insert((k, l), (i, j));
+            if self.goals.contains(&(k, l)) {
+                self.reached = Some((k, l));
+                return StepResult::Found;
+            }
+
+            terrain.set_search_state(k, l, SearchState::Frontier);
+            self.stack.push((k, l));
+        }
+
+        StepResult::Running
+    }
+
+    fn frontier(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn path(&self) -> Option<Vec<(usize, usize)>> {
+        self.reached
+            .map(|goal| reconstruct_path(&self.parents, goal))
+    }
+}
@@ -0,0 +1,98 @@
+/// Dijkstra search, expands cells by increasing distance from the start
+use super::{reconstruct_path, OpenNode, Pathfinder, StepResult};
+use crate::board::{SearchState, Terrain};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+#[derive(Default)]
+pub struct Dijkstra {
+    open: BinaryHeap<OpenNode>,
+    closed: HashSet<(usize, usize)>,
+    distances: HashMap<(usize, usize), f32>,
+    parents: HashMap<(usize, usize), (usize, usize)>,
+    goals: Vec<(usize, usize)>,
+    /// Goal the search got to
+    reached: Option<(usize, usize)>,
+}
+
+impl Pathfinder for Dijkstra {
+    fn name(&self) -> String {
+        "Dijkstra".to_string()
+    }
+
+    fn init(
+        &mut self,
+        _terrain: &Terrain,
+        start: (usize, usize),
+        goals: &[(usize, usize)],
+    ) {
+        self.open = BinaryHeap::from([OpenNode {
+            cost: 0.,
+            tie: 0.,
+            pos: start,
+        }]);
+        self.closed.clear();
+        self.distances = HashMap::from([(start, 0.)]);
+        self.parents.clear();
+        self.goals = goals.to_vec();
+        self.reached = None;
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> StepResult {
+        // skip the outdated copies left in the heap
+        let node = loop {
+            match self.open.pop() {
+                Some(node) if self.closed.contains(&node.pos) => continue,
+                Some(node) => break node,
+                None => return StepResult::NoPath,
+            }
+        };
+
+        if self.goals.contains(&node.pos) {
+            self.reached = Some(node.pos);
+            return StepResult::Found;
+        }
+
+        let (i, j) = node.pos;
+        self.closed.insert(node.pos);
+        terrain.set_search_state(i, j, SearchState::Closed);
+        terrain.expand(i, j, node.cost, node.cost);
+
+        for (k, l) in terrain.get_neighbours(i, j) {
+            if self.closed.contains(&(k, l)) {
+                continue;
+            }
+            let Some(cost) = terrain.move_cost((i, j), (k, l)) else {
+                continue;
+            };
+
+            let distance = node.cost + cost;
+            if self
+                .distances
+                .get(&(k, l))
+                .is_some_and(|&known| known <= distance)
+            {
+                continue;
+            }
+
+            self.distances.insert((k, l), distance);
+            self.parents.insert((k, l), (i, j));
+            self.open.push(OpenNode {
+                cost: distance,
+                tie: 0.,
+                pos: (k, l),
+            });
+            terrain.set_search_state(k, l, SearchState::Frontier);
+        }
+
+        StepResult::Running
+    }
+
+    fn frontier(&self) -> usize {
+        self.distances.len() - self.closed.len()
+    }
+
+    fn path(&self) -> Option<Vec<(usize, usize)>> {
+        self.reached
+            .map(|goal| reconstruct_path(&self.parents, goal))
+    }
+}
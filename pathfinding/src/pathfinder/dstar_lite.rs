@@ -0,0 +1,350 @@
+/// D* Lite, searches backward from the goals and repairs its path when cells
+/// change instead of starting over
+use super::{Heuristic, OpenNode, Pathfinder, StepResult};
+use crate::board::{Connectivity, SearchState, Terrain};
+use std::collections::{BinaryHeap, HashMap};
+
+type Key = (f32, f32);
+
+#[derive(Default)]
+pub struct DStarLite {
+    /// Cost to the closest goal, missing means infinite
+    g: HashMap<(usize, usize), f32>,
+    /// One step lookahead of `g`, the cell is consistent when both agree
+    rhs: HashMap<(usize, usize), f32>,
+    open: BinaryHeap<OpenNode>,
+    /// Current key of the cells in the heap, other copies are outdated
+    queued: HashMap<(usize, usize), Key>,
+    start: (usize, usize),
+    goals: Vec<(usize, usize)>,
+    /// Heuristic offset accumulated when the start moves
+    km: f32,
+    octile: bool,
+    repairing: bool,
+    path: Option<Vec<(usize, usize)>>,
+}
+
+fn is_smaller(a: Key, b: Key) -> bool {
+    a.0 < b.0 || a.0 == b.0 && a.1 < b.1
+}
+
+impl DStarLite {
+    pub fn new() -> DStarLite {
+        DStarLite::default()
+    }
+
+    fn g(&self, pos: (usize, usize)) -> f32 {
+        *self.g.get(&pos).unwrap_or(&f32::INFINITY)
+    }
+
+    fn rhs(&self, pos: (usize, usize)) -> f32 {
+        *self.rhs.get(&pos).unwrap_or(&f32::INFINITY)
+    }
+
+    fn heuristic(&self) -> Heuristic {
+        match self.octile {
+            true => Heuristic::Octile,
+            false => Heuristic::Manhattan,
+        }
+    }
+
+    fn key(&self, pos: (usize, usize)) -> Key {
+        let best = self.g(pos).min(self.rhs(pos));
+        let estimate = self.heuristic().estimate(self.start, pos);
+
+        (best + estimate + self.km, best)
+    }
+
+    /// Recomputes the lookahead of a cell and queues it if inconsistent
+    fn update(&mut self, terrain: &mut Terrain, pos: (usize, usize)) {
+        if !self.goals.contains(&pos) {
+            let rhs = match terrain.is_walkable(pos.0, pos.1) {
+                true => terrain
+                    .get_neighbours(pos.0, pos.1)
+                    .into_iter()
+                    .filter_map(|next| {
+                        Some(terrain.move_cost(pos, next)? + self.g(next))
+                    })
+                    .fold(f32::INFINITY, f32::min),
+                false => f32::INFINITY,
+            };
+            self.rhs.insert(pos, rhs);
+        }
+
+        self.queued.remove(&pos);
+        if self.g(pos) != self.rhs(pos) {
+            self.push(pos);
+            terrain.set_search_state(pos.0, pos.1, SearchState::Frontier);
+        }
+    }
+
+    fn push(&mut self, pos: (usize, usize)) {
+        let key = self.key(pos);
+        self.queued.insert(pos, key);
+        self.open.push(OpenNode {
+            cost: key.0,
+            tie: key.1,
+            pos,
+        });
+    }
+
+    /// Goes down the cost to the goals from the start, gives up on cells
+    /// still waiting to be updated
+    fn follow(&self, terrain: &Terrain) -> Option<Vec<(usize, usize)>> {
+        let mut path = vec![self.start];
+        let mut current = self.start;
+
+        while !self.goals.contains(&current) {
+            let next = terrain
+                .get_neighbours(current.0, current.1)
+                .into_iter()
+                .filter_map(|next| {
+                    let cost = terrain.move_cost(current, next)?;
+                    Some((cost + self.g(next), next))
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0))?
+                .1;
+
+            if self.queued.contains_key(&next) {
+                return None;
+            }
+            path.push(next);
+            current = next;
+        }
+
+        Some(path)
+    }
+
+    /// Smallest up to date entry of the heap
+    fn top(&mut self) -> Option<(Key, (usize, usize))> {
+        while let Some(node) = self.open.peek() {
+            let key = (node.cost, node.tie);
+            if self.queued.get(&node.pos) == Some(&key) {
+                return Some((key, node.pos));
+            }
+            self.open.pop();
+        }
+        None
+    }
+}
+
+impl Pathfinder for DStarLite {
+    fn name(&self) -> String {
+        "D* Lite".to_string()
+    }
+
+    fn init(
+        &mut self,
+        terrain: &Terrain,
+        start: (usize, usize),
+        goals: &[(usize, usize)],
+    ) {
+        self.g.clear();
+        self.rhs = goals.iter().map(|&goal| (goal, 0.)).collect();
+        self.open.clear();
+        self.queued.clear();
+        self.start = start;
+        self.goals = goals.to_vec();
+        self.km = 0.;
+        self.octile = terrain.connectivity == Connectivity::Eight;
+        self.repairing = false;
+        self.path = None;
+
+        for &goal in goals {
+            self.push(goal);
+        }
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> StepResult {
+        let top = self.top();
+
+        // done once the start is consistent and nothing cheaper is queued
+        let start_key = self.key(self.start);
+        let settled = top.is_none_or(|(key, _)| !is_smaller(key, start_key));
+        if settled && self.g(self.start) == self.rhs(self.start) {
+            if self.g(self.start).is_infinite() {
+                self.repairing = false;
+                return StepResult::NoPath;
+            }
+
+            // cells further on the way may still be outdated
+            self.path = self.follow(terrain);
+            if self.path.is_some() {
+                self.repairing = false;
+                return StepResult::Found;
+            }
+        }
+
+        let Some((old_key, pos)) = top else {
+            return StepResult::NoPath;
+        };
+        self.open.pop();
+
+        // the start moved since it was queued
+        if is_smaller(old_key, self.key(pos)) {
+            self.push(pos);
+            return StepResult::Running;
+        }
+        self.queued.remove(&pos);
+
+        if self.g(pos) > self.rhs(pos) {
+            self.g.insert(pos, self.rhs(pos));
+            let estimate = self.heuristic().estimate(self.start, pos);
+            terrain.expand(
+                pos.0,
+                pos.1,
+                self.rhs(pos),
+                self.rhs(pos) + estimate,
+            );
+        } else {
+            self.g.remove(&pos);
+            self.update(terrain, pos);
+        }
+        for previous in terrain.get_neighbours(pos.0, pos.1) {
+            self.update(terrain, previous);
+        }
+
+        let state = match self.repairing {
+            true => SearchState::Updated,
+            false => SearchState::Closed,
+        };
+        terrain.set_search_state(pos.0, pos.1, state);
+
+        StepResult::Running
+    }
+
+    fn frontier(&self) -> usize {
+        self.queued.len()
+    }
+
+    fn path(&self) -> Option<Vec<(usize, usize)>> {
+        self.path.clone()
+    }
+
+    fn repair(
+        &mut self,
+        terrain: &mut Terrain,
+        cells: &[(usize, usize)],
+    ) -> bool {
+        self.repairing = true;
+        self.path = None;
+
+        for &(i, j) in cells {
+            self.update(terrain, (i, j));
+            for previous in terrain.get_neighbours(i, j) {
+                self.update(terrain, previous);
+            }
+            terrain.set_search_state(i, j, SearchState::Updated);
+        }
+
+        true
+    }
+
+    /// Only shifts the keys, cells queued before the move get requeued
+    /// when they come out
+    fn move_start(&mut self, start: (usize, usize)) -> bool {
+        self.km += self.heuristic().estimate(self.start, start);
+        self.start = start;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::CellType;
+    use crate::pathfinder::tests::{obstacles, solve};
+    use crate::pathfinder::Dijkstra;
+    use nannou::rand::rngs::StdRng;
+    use nannou::rand::{Rng, SeedableRng};
+
+    /// Steps a search already started until it's over
+    fn finish(dstar: &mut DStarLite, terrain: &mut Terrain) -> Option<f32> {
+        loop {
+            match dstar.step(terrain) {
+                StepResult::Running => (),
+                StepResult::Found => {
+                    return dstar.path().map(|p| terrain.path_cost(&p))
+                }
+                StepResult::NoPath => return None,
+            }
+        }
+    }
+
+    fn assert_same(cost: Option<f32>, expected: Option<f32>) {
+        match (cost, expected) {
+            (Some(cost), Some(expected)) => {
+                assert!((cost - expected).abs() < 1e-3, "{} {}", cost, expected)
+            }
+            (cost, expected) => assert_eq!(cost, expected),
+        }
+    }
+
+    #[test]
+    fn repairs_find_the_same_cost_as_a_fresh_search() {
+        const TYPES: [CellType; 3] =
+            [CellType::Wall, CellType::Empty, CellType::Water];
+
+        for seed in 0..20 {
+            for connectivity in [Connectivity::Four, Connectivity::Eight] {
+                let mut terrain = obstacles(15, 10, seed);
+                terrain.connectivity = connectivity;
+                let (start, goal) = ((0, 0), (14, 9));
+                let mut dstar = DStarLite::new();
+                solve(&mut dstar, &mut terrain, start, goal);
+
+                // a few edits in a row, each one repaired
+                let mut rng = StdRng::seed_from_u64(seed);
+                for _ in 0..5 {
+                    let cells: Vec<(usize, usize)> = (0..4)
+                        .map(|_| (rng.gen_range(0..15), rng.gen_range(0..10)))
+                        .filter(|&cell| cell != start && cell != goal)
+                        .collect();
+                    for &(i, j) in &cells {
+                        let ctype = TYPES[rng.gen_range(0..TYPES.len())];
+                        terrain.set(i, j, ctype);
+                    }
+
+                    assert!(dstar.repair(&mut terrain, &cells));
+                    let cost = finish(&mut dstar, &mut terrain);
+                    let expected = solve(
+                        &mut Dijkstra::default(),
+                        &mut terrain.clone(),
+                        start,
+                        goal,
+                    );
+                    assert_same(cost, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn moving_the_start_along_the_path_keeps_it_optimal() {
+        for seed in 0..10 {
+            let mut terrain = obstacles(15, 10, seed);
+            let goal = (14, 9);
+            let mut dstar = DStarLite::new();
+            if solve(&mut dstar, &mut terrain, (0, 0), goal).is_none() {
+                continue;
+            }
+
+            // halfway there, the next cell gets walled
+            let path = dstar.path().unwrap();
+            let (start, next) =
+                (path[path.len() / 2], path[path.len() / 2 + 1]);
+            terrain.set(next.0, next.1, CellType::Wall);
+            assert!(dstar.move_start(start));
+            assert!(dstar.repair(&mut terrain, &[next]));
+
+            let cost = finish(&mut dstar, &mut terrain);
+            let expected = solve(
+                &mut Dijkstra::default(),
+                &mut terrain.clone(),
+                start,
+                goal,
+            );
+            assert_same(cost, expected);
+        }
+    }
+}
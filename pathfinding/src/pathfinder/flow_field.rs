@@ -0,0 +1,107 @@
+/// Flow field, the cost to the nearest stop from every cell of the terrain
+/// and the way to go from there, shared by any number of walkers
+use super::OpenNode;
+use crate::board::Terrain;
+use std::collections::BinaryHeap;
+
+pub struct FlowField {
+    h: usize,
+    /// Integration field, infinite where no stop can be reached
+    distances: Vec<f32>,
+    /// Neighbour to go to from each cell, None on stops and dead ends
+    next: Vec<Option<(usize, usize)>>,
+}
+
+impl FlowField {
+    /// Dijkstra from every stop at once, moves are followed backwards so
+    /// that weighted cells cost what they would to a walker
+    pub fn new(terrain: &Terrain, stops: &[(usize, usize)]) -> FlowField {
+        let (w, h) = (terrain.width(), terrain.height());
+        let mut distances = vec![f32::INFINITY; w * h];
+        let mut open = BinaryHeap::new();
+
+        for &(i, j) in stops {
+            distances[i * h + j] = 0.;
+            open.push(OpenNode {
+                cost: 0.,
+                tie: 0.,
+                pos: (i, j),
+            });
+        }
+
+        while let Some(node) = open.pop() {
+            let (k, l) = node.pos;
+            if distances[k * h + l] < node.cost {
+                continue;
+            }
+
+            for (i, j) in terrain.get_neighbours(k, l) {
+                if !terrain.is_walkable(i, j) {
+                    continue;
+                }
+                let Some(cost) = terrain.move_cost((i, j), (k, l)) else {
+                    continue;
+                };
+
+                let distance = node.cost + cost;
+                if distance < distances[i * h + j] {
+                    distances[i * h + j] = distance;
+                    open.push(OpenNode {
+                        cost: distance,
+                        tie: 0.,
+                        pos: (i, j),
+                    });
+                }
+            }
+        }
+
+        // each cell points to the neighbour it would reach the stop through
+        let mut next = vec![None; w * h];
+        for i in 0..w {
+            for j in 0..h {
+                if distances[i * h + j] == 0. || !terrain.is_walkable(i, j) {
+                    continue;
+                }
+
+                next[i * h + j] = terrain
+                    .get_neighbours(i, j)
+                    .into_iter()
+                    .filter_map(|(k, l)| {
+                        let cost = terrain.move_cost((i, j), (k, l))?;
+                        Some(((k, l), cost + distances[k * h + l]))
+                    })
+                    .filter(|(_, distance)| distance.is_finite())
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(cell, _)| cell);
+            }
+        }
+
+        FlowField { h, distances, next }
+    }
+
+    /// Cost to the nearest stop, infinite if there is none
+    pub fn distance(&self, i: usize, j: usize) -> f32 {
+        self.distances[i * self.h + j]
+    }
+
+    pub fn next(&self, i: usize, j: usize) -> Option<(usize, usize)> {
+        self.next[i * self.h + j]
+    }
+
+    /// Unit vector towards the next cell, x along i and y along j
+    pub fn direction(&self, i: usize, j: usize) -> Option<(f32, f32)> {
+        let (k, l) = self.next(i, j)?;
+        let (x, y) = (k as f32 - i as f32, l as f32 - j as f32);
+        let length = (x * x + y * y).sqrt();
+
+        Some((x / length, y / length))
+    }
+
+    /// Cells from which a stop can be reached
+    pub fn reachable(&self) -> Vec<(usize, usize)> {
+        (0..self.distances.len())
+            .filter(|&index| self.distances[index].is_finite())
+            .map(|index| (index / self.h, index % self.h))
+            .collect()
+    }
+}
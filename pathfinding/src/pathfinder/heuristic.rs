@@ -0,0 +1,109 @@
+/// Distance estimations used by informed searches
+use std::f32::consts::SQRT_2;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Heuristic {
+    Manhattan,
+    Euclidean,
+    Chebyshev,
+    Octile,
+}
+
+/// How to order nodes whose cost is the same
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TieBreak {
+    /// Deepest node first, tends to run straight to the goal
+    HighG,
+    /// Shallowest node first, explores the whole plateau
+    LowG,
+    /// Oldest node first
+    Fifo,
+    /// Newest node first
+    Lifo,
+}
+
+/// Everything that can be tuned on A* like searches
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AStarSettings {
+    pub heuristic: Heuristic,
+    /// Multiplies the heuristic, above 1 the path may not be the shortest
+    pub weight: f32,
+    pub tie_break: TieBreak,
+}
+
+/// Weights the settings cycle through
+const WEIGHTS: [f32; 5] = [1., 1.2, 1.5, 2., 5.];
+
+impl Heuristic {
+    /// Estimated distance between two cells
+    pub fn estimate(&self, a: (usize, usize), b: (usize, usize)) -> f32 {
+        let dx = a.0.abs_diff(b.0) as f32;
+        let dy = a.1.abs_diff(b.1) as f32;
+
+        match self {
+            Heuristic::Manhattan => dx + dy,
+            Heuristic::Euclidean => (dx * dx + dy * dy).sqrt(),
+            Heuristic::Chebyshev => dx.max(dy),
+            Heuristic::Octile => dx.max(dy) + (SQRT_2 - 1.) * dx.min(dy),
+        }
+    }
+
+    /// Estimated distance to the closest of several cells
+    pub fn nearest(&self, a: (usize, usize), goals: &[(usize, usize)]) -> f32 {
+        goals
+            .iter()
+            .map(|&b| self.estimate(a, b))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    pub fn next(&self) -> Heuristic {
+        match self {
+            Heuristic::Manhattan => Heuristic::Euclidean,
+            Heuristic::Euclidean => Heuristic::Chebyshev,
+            Heuristic::Chebyshev => Heuristic::Octile,
+            Heuristic::Octile => Heuristic::Manhattan,
+        }
+    }
+}
+
+impl TieBreak {
+    /// Secondary key of an open node, the smallest comes out first
+    pub fn key(&self, g: f32, insertion: usize) -> f32 {
+        match self {
+            TieBreak::HighG => -g,
+            TieBreak::LowG => g,
+            TieBreak::Fifo => insertion as f32,
+            TieBreak::Lifo => -(insertion as f32),
+        }
+    }
+
+    pub fn next(&self) -> TieBreak {
+        match self {
+            TieBreak::HighG => TieBreak::LowG,
+            TieBreak::LowG => TieBreak::Fifo,
+            TieBreak::Fifo => TieBreak::Lifo,
+            TieBreak::Lifo => TieBreak::HighG,
+        }
+    }
+}
+
+impl AStarSettings {
+    /// Goes to the next preset weight, back to 1 after the last one
+    pub fn next_weight(&mut self) {
+        self.weight = WEIGHTS
+            .iter()
+            .copied()
+            .find(|&w| w > self.weight)
+            .unwrap_or(WEIGHTS[0]);
+    }
+}
+
+impl Default for AStarSettings {
+    fn default() -> AStarSettings {
+        AStarSettings {
+            heuristic: Heuristic::Manhattan,
+            weight: 1.,
+            tie_break: TieBreak::HighG,
+        }
+    }
+}
@@ -0,0 +1,609 @@
+/// Jump point search, on 8-connected grids where every move costs the same.
+/// Diagonals never cut corners, whatever the terrain settings are.
+/// JPS+ reads the jumps from a table computed once per map.
+use super::{reconstruct_path, Heuristic, OpenNode, Pathfinder, StepResult};
+use crate::board::{Connectivity, CornerCutting, SearchState, Terrain};
+use std::cell::RefCell;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::rc::Rc;
+
+type Direction = (isize, isize);
+
+const DIRECTIONS: [Direction; 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (-1, 1),
+    (1, -1),
+    (-1, -1),
+];
+
+/// What lies ahead of a cell in one direction, ignoring the goals
+#[derive(Copy, Clone)]
+enum Jump {
+    /// A jump point this many moves away
+    JumpPoint(usize),
+    /// Blocked after this many moves
+    Wall(usize),
+}
+
+type Table = Rc<Vec<[Jump; 8]>>;
+
+thread_local! {
+    /// Table of the last map searched with JPS+ and the map version it was
+    /// made from, every new search on the same map reuses it
+    static TABLE: RefCell<Option<(u64, Table)>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+pub struct JumpPointSearch {
+    plus: bool,
+    /// Jump of every cell in every direction, for JPS+
+    table: Table,
+    open: BinaryHeap<OpenNode>,
+    closed: HashSet<(usize, usize)>,
+    distances: HashMap<(usize, usize), f32>,
+    parents: HashMap<(usize, usize), (usize, usize)>,
+    goals: Vec<(usize, usize)>,
+    /// Goal the search got to
+    reached: Option<(usize, usize)>,
+}
+
+fn is_open(terrain: &Terrain, x: isize, y: isize) -> bool {
+    x >= 0 && y >= 0 && terrain.is_walkable(x as usize, y as usize)
+}
+
+/// Whether going straight through a cell would skip a shorter way around
+/// an obstacle
+fn has_forced(
+    terrain: &Terrain,
+    x: isize,
+    y: isize,
+    (dx, dy): Direction,
+) -> bool {
+    let open = |x, y| is_open(terrain, x, y);
+
+    if dx != 0 {
+        (open(x, y - 1) && !open(x - dx, y - 1))
+            || (open(x, y + 1) && !open(x - dx, y + 1))
+    } else {
+        (open(x - 1, y) && !open(x - 1, y - dy))
+            || (open(x + 1, y) && !open(x + 1, y - dy))
+    }
+}
+
+/// Whether a diagonal move from a cell is allowed
+fn can_go_diagonal(
+    terrain: &Terrain,
+    x: isize,
+    y: isize,
+    (dx, dy): Direction,
+) -> bool {
+    is_open(terrain, x + dx, y)
+        && is_open(terrain, x, y + dy)
+        && is_open(terrain, x + dx, y + dy)
+}
+
+/// Directions worth looking at from a cell, knowing where the search came from
+fn pruned_directions(
+    terrain: &Terrain,
+    (x, y): (isize, isize),
+    parent: Option<(isize, isize)>,
+) -> Vec<Direction> {
+    let open = |x, y| is_open(terrain, x, y);
+
+    let Some((px, py)) = parent else {
+        return DIRECTIONS
+            .into_iter()
+            .filter(|&(dx, dy)| {
+                if dx != 0 && dy != 0 {
+                    can_go_diagonal(terrain, x, y, (dx, dy))
+                } else {
+                    open(x + dx, y + dy)
+                }
+            })
+            .collect();
+    };
+
+    let (dx, dy) = ((x - px).signum(), (y - py).signum());
+    let mut res = Vec::new();
+
+    if dx != 0 && dy != 0 {
+        let horizontal = open(x + dx, y);
+        let vertical = open(x, y + dy);
+
+        if vertical {
+            res.push((0, dy));
+        }
+        if horizontal {
+            res.push((dx, 0));
+        }
+        if horizontal && vertical {
+            res.push((dx, dy));
+        }
+    } else if dx != 0 {
+        let next = open(x + dx, y);
+        let top = open(x, y + 1);
+        let bottom = open(x, y - 1);
+
+        if next {
+            res.push((dx, 0));
+            if top {
+                res.push((dx, 1));
+            }
+            if bottom {
+                res.push((dx, -1));
+            }
+        }
+        if top {
+            res.push((0, 1));
+        }
+        if bottom {
+            res.push((0, -1));
+        }
+    } else {
+        let next = open(x, y + dy);
+        let right = open(x + 1, y);
+        let left = open(x - 1, y);
+
+        if next {
+            res.push((0, dy));
+            if right {
+                res.push((1, dy));
+            }
+            if left {
+                res.push((-1, dy));
+            }
+        }
+        if right {
+            res.push((1, 0));
+        }
+        if left {
+            res.push((-1, 0));
+        }
+    }
+
+    res
+}
+
+impl JumpPointSearch {
+    pub fn new() -> JumpPointSearch {
+        JumpPointSearch::default()
+    }
+
+    pub fn plus() -> JumpPointSearch {
+        JumpPointSearch {
+            plus: true,
+            ..Default::default()
+        }
+    }
+
+    /// Goes straight until something interesting, marking the scanned cells
+    fn scan_straight(
+        &self,
+        terrain: &mut Terrain,
+        (mut x, mut y): (isize, isize),
+        (dx, dy): Direction,
+    ) -> Option<(isize, isize)> {
+        loop {
+            x += dx;
+            y += dy;
+
+            if !is_open(terrain, x, y) {
+                return None;
+            }
+            if self.goals.contains(&(x as usize, y as usize))
+                || has_forced(terrain, x, y, (dx, dy))
+            {
+                return Some((x, y));
+            }
+            mark_scanned(terrain, x, y);
+        }
+    }
+
+    /// Goes diagonally, looking straight ahead on both sides at every cell
+    fn scan_diagonal(
+        &self,
+        terrain: &mut Terrain,
+        (mut x, mut y): (isize, isize),
+        (dx, dy): Direction,
+    ) -> Option<(isize, isize)> {
+        loop {
+            if !can_go_diagonal(terrain, x, y, (dx, dy)) {
+                return None;
+            }
+            x += dx;
+            y += dy;
+
+            if self.goals.contains(&(x as usize, y as usize))
+                || self.scan_straight(terrain, (x, y), (dx, 0)).is_some()
+                || self.scan_straight(terrain, (x, y), (0, dy)).is_some()
+            {
+                return Some((x, y));
+            }
+            mark_scanned(terrain, x, y);
+        }
+    }
+
+    /// Fills the JPS+ table, each cell is computed from the next one
+    fn precompute(&mut self, terrain: &Terrain) {
+        let (w, h) = (terrain.width(), terrain.height());
+        let mut table = vec![[Jump::Wall(0); 8]; w * h];
+
+        // straight directions first, diagonals read them
+        for (index, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+            let mut cells: Vec<(isize, isize)> = (0..w as isize)
+                .flat_map(|x| (0..h as isize).map(move |y| (x, y)))
+                .collect();
+            cells.sort_by_key(|&(x, y)| -(x * dx + y * dy));
+
+            for (x, y) in cells {
+                let (nx, ny) = (x + dx, y + dy);
+                let diagonal = dx != 0 && dy != 0;
+
+                let jump = if diagonal
+                    && !can_go_diagonal(terrain, x, y, (dx, dy))
+                    || !is_open(terrain, nx, ny)
+                {
+                    Jump::Wall(0)
+                } else if !diagonal && has_forced(terrain, nx, ny, (dx, dy))
+                    || diagonal
+                        && (is_jump_point(&table, nx, ny, (dx, 0), h)
+                            || is_jump_point(&table, nx, ny, (0, dy), h))
+                {
+                    Jump::JumpPoint(1)
+                } else {
+                    match table[nx as usize * h + ny as usize][index] {
+                        Jump::JumpPoint(n) => Jump::JumpPoint(n + 1),
+                        Jump::Wall(n) => Jump::Wall(n + 1),
+                    }
+                };
+
+                table[x as usize * h + y as usize][index] = jump;
+            }
+        }
+
+        self.table = Rc::new(table);
+    }
+
+    /// Moves that can be made in a straight line from a cell
+    fn reach(
+        &self,
+        (x, y): (isize, isize),
+        direction: Direction,
+        h: usize,
+    ) -> (usize, bool) {
+        let index = DIRECTIONS.iter().position(|&d| d == direction).unwrap();
+        match self.table[x as usize * h + y as usize][index] {
+            Jump::JumpPoint(n) => (n, true),
+            Jump::Wall(n) => (n, false),
+        }
+    }
+
+    /// Same jumps as the scans, read from the table
+    fn jump_from_table(
+        &self,
+        h: usize,
+        (x, y): (isize, isize),
+        (dx, dy): Direction,
+    ) -> Option<(isize, isize)> {
+        let (reach, jump_point) = self.reach((x, y), (dx, dy), h);
+        let seen = self
+            .goals
+            .iter()
+            .filter_map(|&goal| {
+                self.goal_seen(h, (x, y), (dx, dy), reach, goal)
+            })
+            .min();
+
+        let n = seen.or(jump_point.then_some(reach))?;
+        Some((x + dx * n as isize, y + dy * n as isize))
+    }
+
+    /// Moves after which a goal gets seen going in a direction
+    fn goal_seen(
+        &self,
+        h: usize,
+        (x, y): (isize, isize),
+        (dx, dy): Direction,
+        reach: usize,
+        goal: (usize, usize),
+    ) -> Option<usize> {
+        let (gx, gy) = (goal.0 as isize, goal.1 as isize);
+        // moves needed to reach the goal column and row, if ahead
+        let to_column =
+            (dx != 0 && (gx - x) * dx > 0).then(|| ((gx - x) * dx) as usize);
+        let to_row =
+            (dy != 0 && (gy - y) * dy > 0).then(|| ((gy - y) * dy) as usize);
+
+        if dx == 0 || dy == 0 {
+            let on_line = if dx != 0 { gy == y } else { gx == x };
+            let distance = if dx != 0 { to_column } else { to_row };
+
+            return distance.filter(|&n| on_line && n <= reach);
+        }
+
+        // the goal is seen when crossing its row or its column
+        let mut crossings: Vec<usize> = [to_column, to_row]
+            .into_iter()
+            .flatten()
+            .filter(|&n| n <= reach)
+            .collect();
+        crossings.sort();
+
+        crossings.into_iter().find(|&n| {
+            let (cx, cy) = (x + dx * n as isize, y + dy * n as isize);
+            if cx == gx && cy == gy {
+                true
+            } else if cx == gx {
+                (gy - cy) * dy > 0
+                    && (((gy - cy) * dy) as usize)
+                        <= self.reach((cx, cy), (0, dy), h).0
+            } else if cy == gy {
+                (gx - cx) * dx > 0
+                    && (((gx - cx) * dx) as usize)
+                        <= self.reach((cx, cy), (dx, 0), h).0
+            } else {
+                false
+            }
+        })
+    }
+}
+
+fn is_jump_point(
+    table: &[[Jump; 8]],
+    x: isize,
+    y: isize,
+    direction: Direction,
+    h: usize,
+) -> bool {
+    let index = DIRECTIONS.iter().position(|&d| d == direction).unwrap();
+    matches!(
+        table[x as usize * h + y as usize][index],
+        Jump::JumpPoint(_)
+    )
+}
+
+fn mark_scanned(terrain: &mut Terrain, x: isize, y: isize) {
+    let (i, j) = (x as usize, y as usize);
+    if terrain.search_state(i, j) == Some(SearchState::Unvisited) {
+        terrain.set_search_state(i, j, SearchState::Scanned);
+    }
+}
+
+impl Pathfinder for JumpPointSearch {
+    fn name(&self) -> String {
+        match self.plus {
+            false => "Jump point search".to_string(),
+            true => "JPS+".to_string(),
+        }
+    }
+
+    fn init(
+        &mut self,
+        terrain: &Terrain,
+        start: (usize, usize),
+        goals: &[(usize, usize)],
+    ) {
+        if terrain.connectivity != Connectivity::Eight
+            || terrain.corner_cutting != CornerCutting::BothSidesOpen
+        {
+            println!("Jump point search always uses 8 neighbours without cutting corners");
+        }
+        let weighted = (0..terrain.width()).any(|i| {
+            (0..terrain.height())
+                .any(|j| terrain.cost(i, j).is_some_and(|cost| cost != 1.))
+        });
+        if weighted {
+            println!("Jump point search ignores the cell weights, every move costs the same");
+        }
+
+        self.open = BinaryHeap::from([OpenNode {
+            cost: Heuristic::Octile.nearest(start, goals),
+            tie: 0.,
+            pos: start,
+        }]);
+        self.closed.clear();
+        self.distances = HashMap::from([(start, 0.)]);
+        self.parents.clear();
+        self.goals = goals.to_vec();
+        self.reached = None;
+
+        self.prepare(terrain);
+    }
+
+    fn prepare(&mut self, terrain: &Terrain) {
+        if !self.plus {
+            return;
+        }
+
+        let version = terrain.map_version();
+        let cached = TABLE.with_borrow(|cached| {
+            cached
+                .as_ref()
+                .filter(|(made_from, _)| *made_from == version)
+                .map(|(_, table)| table.clone())
+        });
+        match cached {
+            Some(table) => self.table = table,
+            None => {
+                self.precompute(terrain);
+                TABLE.set(Some((version, self.table.clone())));
+            }
+        }
+    }
+
+    fn step(&mut self, terrain: &mut Terrain) -> StepResult {
+        // skip the outdated copies left in the heap
+        let ((i, j), f) = loop {
+            match self.open.pop() {
+                Some(node) if self.closed.contains(&node.pos) => continue,
+                Some(node) => break (node.pos, node.cost),
+                None => return StepResult::NoPath,
+            }
+        };
+
+        if self.goals.contains(&(i, j)) {
+            self.reached = Some((i, j));
+            return StepResult::Found;
+        }
+
+        self.closed.insert((i, j));
+        terrain.set_search_state(i, j, SearchState::Closed);
+        let g = self.distances[&(i, j)];
+        terrain.expand(i, j, g, f);
+
+        let node = (i as isize, j as isize);
+        let parent = self
+            .parents
+            .get(&(i, j))
+            .map(|&(x, y)| (x as isize, y as isize));
+
+        for direction in pruned_directions(terrain, node, parent) {
+            let diagonal = direction.0 != 0 && direction.1 != 0;
+            let jump = if self.plus {
+                self.jump_from_table(terrain.height(), node, direction)
+            } else if diagonal {
+                self.scan_diagonal(terrain, node, direction)
+            } else {
+                self.scan_straight(terrain, node, direction)
+            };
+
+            let Some((x, y)) = jump else {
+                continue;
+            };
+            let (k, l) = (x as usize, y as usize);
+            if self.closed.contains(&(k, l)) {
+                continue;
+            }
+
+            let distance = g + Heuristic::Octile.estimate((i, j), (k, l));
+            if self
+                .distances
+                .get(&(k, l))
+                .is_some_and(|&known| known <= distance)
+            {
+                continue;
+            }
+
+            self.distances.insert((k, l), distance);
+            self.parents.insert((k, l), (i, j));
+            self.open.push(OpenNode {
+                cost: distance + Heuristic::Octile.nearest((k, l), &self.goals),
+                tie: -distance,
+                pos: (k, l),
+            });
+            terrain.set_search_state(k, l, SearchState::JumpPoint);
+        }
+
+        StepResult::Running
+    }
+
+    /// Jump points reached but not expanded yet
+    fn frontier(&self) -> usize {
+        self.distances.len() - self.closed.len()
+    }
+
+    /// Jump points are linked back with every cell in between
+    fn path(&self) -> Option<Vec<(usize, usize)>> {
+        let jump_points = reconstruct_path(&self.parents, self.reached?);
+        let mut path = vec![jump_points[0]];
+
+        for &(x, y) in &jump_points[1..] {
+            let (mut i, mut j) = *path.last().unwrap();
+            while (i, j) != (x, y) {
+                i = (i as isize + (x as isize - i as isize).signum()) as usize;
+                j = (j as isize + (y as isize - j as isize).signum()) as usize;
+                path.push((i, j));
+            }
+        }
+
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::CellType;
+    use crate::generator::MazeAlgorithm;
+    use crate::pathfinder::tests::{obstacles, solve};
+    use crate::pathfinder::{AStar, AStarSettings};
+
+    /// Cost of the JPS and JPS+ paths against A* between two cells
+    fn check(
+        terrain: &mut Terrain,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) {
+        terrain.connectivity = Connectivity::Eight;
+        terrain.corner_cutting = CornerCutting::BothSidesOpen;
+        let settings = AStarSettings {
+            heuristic: Heuristic::Octile,
+            ..Default::default()
+        };
+        let expected = solve(&mut AStar::new(settings), terrain, start, goal);
+
+        for mut jps in [JumpPointSearch::new(), JumpPointSearch::plus()] {
+            let cost = solve(&mut jps, terrain, start, goal);
+            match (cost, expected) {
+                (Some(cost), Some(expected)) => {
+                    assert!((cost - expected).abs() < 1e-3)
+                }
+                (cost, expected) => assert_eq!(cost, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn same_cost_as_astar_on_open_grids() {
+        let mut terrain = Terrain::with_size(13, 8, 1.);
+        for goal in [(12, 7), (12, 0), (0, 7), (6, 3), (0, 0)] {
+            check(&mut terrain, (0, 0), goal);
+            check(&mut terrain, (5, 4), goal);
+        }
+    }
+
+    #[test]
+    fn same_cost_as_astar_on_mazes() {
+        for seed in 0..10 {
+            let mut terrain = Terrain::with_size(21, 15, 1.);
+            let mut generator = MazeAlgorithm::Backtracker.build(seed);
+            generator.init(&mut terrain);
+            while generator.step(&mut terrain) {}
+
+            let open: Vec<(usize, usize)> = (0..21)
+                .flat_map(|i| (0..15).map(move |j| (i, j)))
+                .filter(|&(i, j)| terrain.is_walkable(i, j))
+                .collect();
+            check(&mut terrain, open[0], open[open.len() - 1]);
+        }
+    }
+
+    #[test]
+    fn same_cost_as_astar_around_obstacles() {
+        for seed in 0..20 {
+            let mut terrain = obstacles(15, 10, seed);
+            check(&mut terrain, (0, 0), (14, 9));
+            check(&mut terrain, (14, 9), (0, 0));
+        }
+    }
+
+    #[test]
+    fn table_is_made_once_per_map() {
+        let mut terrain = obstacles(15, 10, 0);
+        let mut jps = JumpPointSearch::plus();
+        jps.init(&terrain, (0, 0), &[(14, 9)]);
+        let table = jps.table.clone();
+
+        // searching leaves the map as it is
+        solve(&mut jps, &mut terrain, (0, 0), (14, 9));
+        assert!(Rc::ptr_eq(&table, &jps.table));
+
+        terrain.set(7, 5, CellType::Wall);
+        jps.init(&terrain, (0, 0), &[(14, 9)]);
+        assert!(!Rc::ptr_eq(&table, &jps.table));
+    }
+}
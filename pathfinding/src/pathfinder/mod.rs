@@ -0,0 +1,247 @@
+/// Pathfinding algorithms, all run one step at a time
+mod astar;
+mod bfs;
+mod bidirectional;
+pub mod cbs;
+mod dfs;
+mod dijkstra;
+mod dstar_lite;
+pub mod flow_field;
+mod heuristic;
+mod jps;
+pub mod route;
+
+pub use astar::AStar;
+pub use bfs::BreadthFirst;
+pub use bidirectional::Bidirectional;
+pub use dfs::DepthFirst;
+pub use dijkstra::Dijkstra;
+pub use dstar_lite::DStarLite;
+pub use heuristic::{AStarSettings, Heuristic};
+pub use jps::JumpPointSearch;
+pub use route::GoalMode;
+
+use crate::board::Terrain;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Outcome of a single search step
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum StepResult {
+    Running,
+    Found,
+    NoPath,
+}
+
+/// A search that can be advanced one node expansion at a time.
+/// `step` reads the terrain and writes its progress in the search layer.
+pub trait Pathfinder {
+    fn name(&self) -> String;
+
+    fn init(
+        &mut self,
+        terrain: &Terrain,
+        start: (usize, usize),
+        goals: &[(usize, usize)],
+    );
+
+    /// Work that only depends on the map, done once per map rather than
+    /// at every init, which calls it anyway
+    fn prepare(&mut self, _terrain: &Terrain) {}
+
+    fn step(&mut self, terrain: &mut Terrain) -> StepResult;
+
+    /// Cells found but not expanded yet
+    fn frontier(&self) -> usize;
+
+    /// Path from start to goal, once the goal has been found
+    fn path(&self) -> Option<Vec<(usize, usize)>>;
+
+    /// Takes edited cells into account so that stepping again repairs the
+    /// path, returns false when the search has to start over instead
+    fn repair(
+        &mut self,
+        _terrain: &mut Terrain,
+        _cells: &[(usize, usize)],
+    ) -> bool {
+        false
+    }
+
+    /// Moves the start of a search able to repair its path, returns false
+    /// when the search has to start over instead
+    fn move_start(&mut self, _start: (usize, usize)) -> bool {
+        false
+    }
+}
+
+/// Algorithms that can be selected at runtime
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Algorithm {
+    BreadthFirst,
+    DepthFirst,
+    Dijkstra,
+    AStar,
+    BidirectionalBfs,
+    BidirectionalAStar,
+    JumpPoint,
+    JumpPointPlus,
+    DStarLite,
+}
+
+impl Algorithm {
+    pub fn build(&self, settings: &AStarSettings) -> Box<dyn Pathfinder> {
+        match self {
+            Algorithm::BreadthFirst => Box::new(BreadthFirst::default()),
+            Algorithm::DepthFirst => Box::new(DepthFirst::default()),
+            Algorithm::Dijkstra => Box::new(Dijkstra::default()),
+            Algorithm::AStar => Box::new(AStar::new(*settings)),
+            Algorithm::BidirectionalBfs => {
+                Box::new(Bidirectional::breadth_first())
+            }
+            Algorithm::BidirectionalAStar => {
+                Box::new(Bidirectional::astar(*settings))
+            }
+            Algorithm::JumpPoint => Box::new(JumpPointSearch::new()),
+            Algorithm::JumpPointPlus => Box::new(JumpPointSearch::plus()),
+            Algorithm::DStarLite => Box::new(DStarLite::new()),
+        }
+    }
+
+    pub fn next(&self) -> Algorithm {
+        match self {
+            Algorithm::BreadthFirst => Algorithm::DepthFirst,
+            Algorithm::DepthFirst => Algorithm::Dijkstra,
+            Algorithm::Dijkstra => Algorithm::AStar,
+            Algorithm::AStar => Algorithm::BidirectionalBfs,
+            Algorithm::BidirectionalBfs => Algorithm::BidirectionalAStar,
+            Algorithm::BidirectionalAStar => Algorithm::JumpPoint,
+            Algorithm::JumpPoint => Algorithm::JumpPointPlus,
+            Algorithm::JumpPointPlus => Algorithm::DStarLite,
+            Algorithm::DStarLite => Algorithm::BreadthFirst,
+        }
+    }
+}
+
+/// Walks back the parent map from `goal`, returns the path from start to goal
+pub fn reconstruct_path(
+    parents: &HashMap<(usize, usize), (usize, usize)>,
+    goal: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let mut path = vec![goal];
+    let mut current = goal;
+
+    while let Some(&parent) = parents.get(&current) {
+        path.push(parent);
+        current = parent;
+    }
+
+    path.reverse();
+    path
+}
+
+/// Cost from the start of a cell about to be expanded, for searches that
+/// don't keep distances: its parent was expanded before it
+pub fn expanded_cost(
+    terrain: &Terrain,
+    parent: Option<&(usize, usize)>,
+    pos: (usize, usize),
+) -> f32 {
+    let Some(&parent) = parent else {
+        return 0.;
+    };
+    let before = terrain.expansion(parent.0, parent.1).map_or(0., |e| e.g);
+
+    before + terrain.move_cost(parent, pos).unwrap_or(0.)
+}
+
+/// Node waiting in a priority queue, the smallest cost comes out first,
+/// then the smallest tie
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct OpenNode {
+    pub cost: f32,
+    pub tie: f32,
+    pub pos: (usize, usize),
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed so that BinaryHeap behaves as a min-heap
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.tie.total_cmp(&self.tie))
+            .then_with(|| other.pos.cmp(&self.pos))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::board::CellType;
+    use nannou::rand::rngs::StdRng;
+    use nannou::rand::{Rng, SeedableRng};
+
+    /// Walls on about a quarter of the cells, the corners stay open
+    pub fn obstacles(w: usize, h: usize, seed: u64) -> Terrain {
+        let mut terrain = Terrain::with_size(w, h, 1.);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for i in 0..w {
+            for j in 0..h {
+                if rng.gen_bool(0.25) {
+                    terrain.set(i, j, CellType::Wall);
+                }
+            }
+        }
+        for (i, j) in [(0, 0), (w - 1, h - 1)] {
+            terrain.set(i, j, CellType::Empty);
+        }
+
+        terrain
+    }
+
+    /// Runs a search to the end, with the cost of the path found
+    pub fn solve(
+        pathfinder: &mut dyn Pathfinder,
+        terrain: &mut Terrain,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> Option<f32> {
+        terrain.reset_search();
+        pathfinder.init(terrain, start, &[goal]);
+
+        loop {
+            match pathfinder.step(terrain) {
+                StepResult::Running => (),
+                StepResult::Found => {
+                    return pathfinder.path().map(|p| terrain.path_cost(&p))
+                }
+                StepResult::NoPath => return None,
+            }
+        }
+    }
+
+    #[test]
+    fn start_on_the_goal_costs_nothing() {
+        let mut algorithm = Algorithm::BreadthFirst;
+        loop {
+            let mut terrain = obstacles(8, 8, 0);
+            let mut pathfinder = algorithm.build(&AStarSettings::default());
+            let cost = solve(pathfinder.as_mut(), &mut terrain, (0, 0), (0, 0));
+            assert_eq!(cost, Some(0.), "{:?}", algorithm);
+
+            algorithm = algorithm.next();
+            if algorithm == Algorithm::BreadthFirst {
+                break;
+            }
+        }
+    }
+}
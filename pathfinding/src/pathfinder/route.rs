@@ -0,0 +1,330 @@
+/// Routes from the start through several stops, the order is searched once
+/// the distances between every pair of waypoints are known
+use super::{reconstruct_path, OpenNode};
+use crate::board::Terrain;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Stops up to which every order is tried, 2-opt improves larger routes
+const EXACT_STOPS: usize = 10;
+
+/// How a search answers when there are several stops
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GoalMode {
+    /// Path to whichever stop is the closest
+    Nearest,
+    /// Route from the start through every stop
+    VisitAll,
+}
+
+impl GoalMode {
+    pub fn next(&self) -> GoalMode {
+        match self {
+            GoalMode::Nearest => GoalMode::VisitAll,
+            GoalMode::VisitAll => GoalMode::Nearest,
+        }
+    }
+}
+
+pub struct Route {
+    /// Stops in visiting order
+    pub stops: Vec<(usize, usize)>,
+    /// Stops the start can't reach, left out of the route
+    pub unreachable: Vec<(usize, usize)>,
+    /// Cells from the start through every stop
+    pub path: Vec<(usize, usize)>,
+    pub cost: f32,
+    /// Whether no other order is cheaper
+    pub exact: bool,
+}
+
+/// Distances and parents of every cell reachable from a waypoint
+struct Exploration {
+    distances: HashMap<(usize, usize), f32>,
+    parents: HashMap<(usize, usize), (usize, usize)>,
+}
+
+/// Dijkstra without a goal
+fn explore(terrain: &Terrain, from: (usize, usize)) -> Exploration {
+    let mut distances = HashMap::from([(from, 0.)]);
+    let mut parents = HashMap::new();
+    let mut open = BinaryHeap::from([OpenNode {
+        cost: 0.,
+        tie: 0.,
+        pos: from,
+    }]);
+
+    while let Some(node) = open.pop() {
+        let (i, j) = node.pos;
+        if distances[&(i, j)] < node.cost {
+            continue;
+        }
+
+        for (k, l) in terrain.get_neighbours(i, j) {
+            let Some(cost) = terrain.move_cost((i, j), (k, l)) else {
+                continue;
+            };
+
+            let distance = node.cost + cost;
+            if distances
+                .get(&(k, l))
+                .is_some_and(|&known| known <= distance)
+            {
+                continue;
+            }
+
+            distances.insert((k, l), distance);
+            parents.insert((k, l), (i, j));
+            open.push(OpenNode {
+                cost: distance,
+                tie: 0.,
+                pos: (k, l),
+            });
+        }
+    }
+
+    Exploration { distances, parents }
+}
+
+/// Cost of going from the start (0) through the waypoints in order
+fn order_cost(legs: &[Vec<f32>], order: &[usize]) -> f32 {
+    let mut previous = 0;
+
+    order
+        .iter()
+        .map(|&next| {
+            let cost = legs[previous][next];
+            previous = next;
+            cost
+        })
+        .sum()
+}
+
+/// Held-Karp, best order ending on each waypoint of each subset
+fn exact_order(legs: &[Vec<f32>]) -> Vec<usize> {
+    let n = legs.len() - 1;
+    let full = (1 << n) - 1;
+    let mut best = vec![vec![f32::INFINITY; n]; 1 << n];
+    let mut previous = vec![vec![usize::MAX; n]; 1 << n];
+
+    for last in 0..n {
+        best[1 << last][last] = legs[0][last + 1];
+    }
+
+    for subset in 1..=full {
+        for last in (0..n).filter(|&last| subset & (1 << last) != 0) {
+            let cost = best[subset][last];
+            if cost.is_infinite() {
+                continue;
+            }
+
+            for next in (0..n).filter(|&next| subset & (1 << next) == 0) {
+                let larger = subset | (1 << next);
+                let candidate = cost + legs[last + 1][next + 1];
+                if candidate < best[larger][next] {
+                    best[larger][next] = candidate;
+                    previous[larger][next] = last;
+                }
+            }
+        }
+    }
+
+    let mut last = (0..n)
+        .min_by(|&a, &b| best[full][a].total_cmp(&best[full][b]))
+        .unwrap();
+    let mut subset = full;
+    let mut order = Vec::new();
+
+    while last != usize::MAX {
+        order.push(last + 1);
+        let before = previous[subset][last];
+        subset &= !(1 << last);
+        last = before;
+    }
+
+    order.reverse();
+    order
+}
+
+/// Nearest waypoint first, then segments get reversed while it helps
+fn two_opt_order(legs: &[Vec<f32>]) -> Vec<usize> {
+    let n = legs.len() - 1;
+    let mut order = Vec::new();
+    let mut current = 0;
+
+    while order.len() < n {
+        let next = (1..=n)
+            .filter(|next| !order.contains(next))
+            .min_by(|&a, &b| legs[current][a].total_cmp(&legs[current][b]))
+            .unwrap();
+        order.push(next);
+        current = next;
+    }
+
+    let mut cost = order_cost(legs, &order);
+    let mut improved = true;
+
+    while improved {
+        improved = false;
+
+        for a in 0..n - 1 {
+            for b in a + 1..n {
+                order[a..=b].reverse();
+                let candidate = order_cost(legs, &order);
+
+                // moves cost the cell entered, so reversing a segment can
+                // change its cost too
+                if candidate < cost - 1e-4 {
+                    cost = candidate;
+                    improved = true;
+                } else {
+                    order[a..=b].reverse();
+                }
+            }
+        }
+    }
+
+    order
+}
+
+/// Orders the stops and joins them from the start
+pub fn plan(
+    terrain: &Terrain,
+    start: (usize, usize),
+    stops: &[(usize, usize)],
+) -> Route {
+    // one exploration from each waypoint gives the legs between all of them
+    let mut explorations = vec![explore(terrain, start)];
+    let (reachable, mut unreachable): (Vec<_>, Vec<_>) = stops
+        .iter()
+        .copied()
+        .partition(|stop| explorations[0].distances.contains_key(stop));
+
+    let waypoints: Vec<(usize, usize)> =
+        std::iter::once(start).chain(reachable).collect();
+    explorations
+        .extend(waypoints[1..].iter().map(|&from| explore(terrain, from)));
+    let legs: Vec<Vec<f32>> = explorations
+        .iter()
+        .map(|exploration| {
+            waypoints
+                .iter()
+                // a waypoint the exploration never got to can't be reached
+                .map(|to| {
+                    exploration
+                        .distances
+                        .get(to)
+                        .copied()
+                        .unwrap_or(f32::INFINITY)
+                })
+                .collect()
+        })
+        .collect();
+
+    let exact = waypoints.len() - 1 <= EXACT_STOPS;
+    let order = match waypoints.len() {
+        1 => Vec::new(),
+        _ if exact => exact_order(&legs),
+        _ => two_opt_order(&legs),
+    };
+
+    // waypoints left out of the order or cut off from the one before are
+    // skipped, the route goes on from the last one reached
+    unreachable.extend(
+        (1..waypoints.len())
+            .filter(|k| !order.contains(k))
+            .map(|k| waypoints[k]),
+    );
+    let mut path = vec![start];
+    let mut visited = Vec::new();
+    let mut cost = 0.;
+    let mut previous = 0;
+    for &next in &order {
+        if legs[previous][next].is_infinite() {
+            unreachable.push(waypoints[next]);
+            continue;
+        }
+
+        let leg =
+            reconstruct_path(&explorations[previous].parents, waypoints[next]);
+        path.extend(leg.into_iter().skip(1));
+        cost += legs[previous][next];
+        visited.push(waypoints[next]);
+        previous = next;
+    }
+
+    Route {
+        stops: visited,
+        unreachable,
+        path,
+        cost,
+        exact,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::rand::rngs::StdRng;
+    use nannou::rand::{Rng, SeedableRng};
+
+    /// Lowest cost over every order of the waypoints
+    fn brute_force(legs: &[Vec<f32>], order: &mut Vec<usize>) -> f32 {
+        let n = legs.len() - 1;
+        if order.len() == n {
+            return order_cost(legs, order);
+        }
+
+        let mut best = f32::INFINITY;
+        for next in 1..=n {
+            if !order.contains(&next) {
+                order.push(next);
+                best = best.min(brute_force(legs, order));
+                order.pop();
+            }
+        }
+        best
+    }
+
+    /// Legs between points of a plane, so that they behave like distances
+    fn random_legs(rng: &mut StdRng, waypoints: usize) -> Vec<Vec<f32>> {
+        let points: Vec<(f32, f32)> = (0..=waypoints)
+            .map(|_| (rng.gen_range(0. ..20.), rng.gen_range(0. ..20.)))
+            .collect();
+
+        points
+            .iter()
+            .map(|a| {
+                points
+                    .iter()
+                    .map(|b| (a.0 - b.0).abs() + (a.1 - b.1).abs())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn held_karp_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for waypoints in 1..=7 {
+            for _ in 0..10 {
+                let legs = random_legs(&mut rng, waypoints);
+                let order = exact_order(&legs);
+
+                let mut sorted = order.clone();
+                sorted.sort();
+                assert_eq!(sorted, (1..=waypoints).collect::<Vec<_>>());
+
+                let expected = brute_force(&legs, &mut Vec::new());
+                assert!((order_cost(&legs, &order) - expected).abs() < 1e-3);
+
+                // the heuristic order visits everything, never better
+                let mut heuristic = two_opt_order(&legs);
+                let cost = order_cost(&legs, &heuristic);
+                heuristic.sort();
+                assert_eq!(heuristic, sorted);
+                assert!(cost >= expected - 1e-3);
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+/// Import and export of maps as PNG images, so that they can be drawn in
+/// any image editor
+use crate::board::{CellType, Terrain};
+use nannou::image::{self, Rgb, RgbImage};
+use std::io;
+use std::path::Path;
+
+/// Pixels darker than this become walls
+const DARK: f32 = 64.;
+/// Pixels more transparent than this are left empty
+const TRANSPARENT: u8 = 0x80;
+
+/// Cell types found by their colour, the ones drawn on the board
+const PALETTE: [CellType; 5] = [
+    CellType::Empty,
+    CellType::Grass,
+    CellType::Sand,
+    CellType::Water,
+    CellType::Mud,
+];
+
+impl CellType {
+    /// Pure green and red are the start and stop, dark pixels are walls
+    /// and the others get the closest colour of the palette
+    pub fn from_pixel(pixel: [u8; 3]) -> CellType {
+        let [r, g, b] = pixel;
+        match (r, g, b) {
+            (0x00, 0xFF, 0x00) => return CellType::Start,
+            (0xFF, 0x00, 0x00) => return CellType::Stop,
+            _ => (),
+        }
+        let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        if luma < DARK {
+            return CellType::Wall;
+        }
+
+        let distance = |ctype: &CellType| {
+            let color = ctype.color();
+            let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+            d(color.red, r) + d(color.green, g) + d(color.blue, b)
+        };
+        PALETTE.into_iter().min_by_key(distance).unwrap()
+    }
+
+    /// Colour of the cell as drawn, but for the start and stop which are
+    /// saved as pure green and red
+    pub fn pixel(&self) -> [u8; 3] {
+        match self {
+            CellType::Start => [0x00, 0xFF, 0x00],
+            CellType::Stop => [0xFF, 0x00, 0x00],
+            _ => {
+                let color = self.color();
+                [color.red, color.green, color.blue]
+            }
+        }
+    }
+}
+
+impl Terrain {
+    /// Reads an image as a board of w x h cells, one cell per pixel when
+    /// no size is given, every green pixel is a start unless there is a
+    /// single one
+    pub fn load_png(
+        path: &Path,
+        size: Option<(usize, usize)>,
+        single_start: bool,
+    ) -> io::Result<Terrain> {
+        let image = image::open(path)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .to_rgba8();
+        let (w, h) =
+            size.unwrap_or((image.width() as usize, image.height() as usize));
+        let mut terrain = Terrain::with_size(w, h, 1.);
+        terrain.single_start = single_start;
+
+        for i in 0..w {
+            for j in 0..h {
+                // pixel at the center of the cell, rows of an image go down
+                let x = (i as f32 + 0.5) * image.width() as f32 / w as f32;
+                let y = (h - j) as f32 - 0.5;
+                let y = y * image.height() as f32 / h as f32;
+                let pixel = image.get_pixel(x as u32, y as u32);
+
+                if pixel[3] >= TRANSPARENT {
+                    let rgb = [pixel[0], pixel[1], pixel[2]];
+                    terrain.set(i, j, CellType::from_pixel(rgb));
+                }
+            }
+        }
+
+        Ok(terrain)
+    }
+
+    /// Writes the board as an image of one pixel per cell
+    pub fn save_png(&self, path: &Path) -> io::Result<()> {
+        let (w, h) = (self.width(), self.height());
+        let image = RgbImage::from_fn(w as u32, h as u32, |x, y| {
+            let (i, j) = (x as usize, h - 1 - y as usize);
+            Rgb(self.get(i, j).unwrap().ctype.pixel())
+        });
+
+        image.save(path).map_err(io::Error::other)
+    }
+}
+
+/// Whether a file is an image rather than a .map file
+pub fn is_png(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    const TYPES: [CellType; 8] = [
+        CellType::Empty,
+        CellType::Wall,
+        CellType::Start,
+        CellType::Stop,
+        CellType::Grass,
+        CellType::Sand,
+        CellType::Water,
+        CellType::Mud,
+    ];
+
+    #[test]
+    fn png_round_trip() {
+        let mut terrain = Terrain::with_size(4, 3, 1.);
+        terrain.single_start = false;
+        for (n, &ctype) in TYPES.iter().enumerate() {
+            terrain.set(n % 4, n / 4, ctype);
+        }
+        terrain.set(3, 2, CellType::Start);
+
+        let path = env::temp_dir().join("pathfinding-round-trip.png");
+        terrain.save_png(&path).unwrap();
+        let single = Terrain::load_png(&path, None, true).unwrap();
+        let several = Terrain::load_png(&path, None, false).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((several.width(), several.height()), (4, 3));
+        for i in 0..4 {
+            for j in 0..3 {
+                assert_eq!(
+                    several.get(i, j).unwrap().ctype,
+                    terrain.get(i, j).unwrap().ctype
+                );
+            }
+        }
+        // a single start is kept, the last one read
+        assert_eq!(single.starts.len(), 1);
+        assert_eq!(single.stops, vec![(3, 0)]);
+        // every start is kept when planning for several agents
+        assert_eq!(several.starts, vec![(2, 0), (3, 2)]);
+    }
+}
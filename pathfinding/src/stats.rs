@@ -0,0 +1,150 @@
+/// Statistics of the current search, drawn over the window and logged once
+/// the search is over
+use crate::board::Terrain;
+use crate::pathfinder::{Pathfinder, StepResult};
+use nannou::prelude::*;
+use once_cell::sync::Lazy;
+use std::time::Duration;
+
+const LINE_HEIGHT: f32 = 18.;
+/// Height of the stats drawn in a panel, on four rows
+pub const PANEL_HEIGHT: f32 = LINE_HEIGHT * 4. + 10.;
+
+static COLOR_HUD: Lazy<Rgba8> =
+    Lazy::new(|| Rgba8::new(0x00, 0x00, 0x00, 0xB0));
+
+#[derive(Clone)]
+pub struct Stats {
+    pub algorithm: String,
+    pub expanded: usize,
+    pub frontier: usize,
+    /// Cost from the start of the last expanded cell, then of the path
+    pub cost: f32,
+    /// Cells of the path once found
+    pub length: Option<usize>,
+    /// Time spent in the search steps, not waiting between ticks
+    pub elapsed: Duration,
+    pub result: StepResult,
+}
+
+impl Stats {
+    pub fn new(pathfinder: &dyn Pathfinder) -> Stats {
+        Stats {
+            algorithm: pathfinder.name(),
+            expanded: 0,
+            frontier: pathfinder.frontier(),
+            cost: 0.,
+            length: None,
+            elapsed: Duration::ZERO,
+            result: StepResult::Running,
+        }
+    }
+
+    /// Takes a step of the search into account
+    pub fn update(
+        &mut self,
+        pathfinder: &dyn Pathfinder,
+        terrain: &Terrain,
+        elapsed: Duration,
+        result: StepResult,
+    ) {
+        self.expanded = terrain.expanded();
+        self.frontier = pathfinder.frontier();
+        self.elapsed += elapsed;
+        self.result = result;
+        if let Some(expansion) = terrain.latest_expansion() {
+            self.cost = expansion.g;
+        }
+    }
+
+    /// The path the search ended with
+    pub fn found(&mut self, terrain: &Terrain, path: &[(usize, usize)]) {
+        self.cost = terrain.path_cost(path);
+        self.length = Some(path.len());
+    }
+
+    /// Stats as key=value pairs on a single line, easy to grep or parse
+    pub fn log_line(&self) -> String {
+        let result = match self.result {
+            StepResult::Running => "running",
+            StepResult::Found => "found",
+            StepResult::NoPath => "no_path",
+        };
+        let length = self.length.map_or("none".to_string(), |n| n.to_string());
+
+        format!(
+            "search algorithm=\"{}\" result={} expanded={} frontier={} \
+             cost={:.3} length={} time_ms={:.3}",
+            self.algorithm,
+            result,
+            self.expanded,
+            self.frontier,
+            self.cost,
+            length,
+            self.elapsed.as_secs_f64() * 1000.
+        )
+    }
+
+    fn lines(&self) -> [String; 6] {
+        let status = match self.result {
+            StepResult::Running => "searching",
+            StepResult::Found => "path found",
+            StepResult::NoPath => "no path",
+        };
+        let length = match self.length {
+            Some(length) => format!("{} cells", length),
+            None => "-".to_string(),
+        };
+
+        [
+            format!("{} ({})", self.algorithm, status),
+            format!("Expanded: {}", self.expanded),
+            format!("Frontier: {}", self.frontier),
+            format!("Cost: {:.2}", self.cost),
+            format!("Length: {}", length),
+            format!("Time: {:.2} ms", self.elapsed.as_secs_f64() * 1000.),
+        ]
+    }
+
+    /// Draws the stats in the top left corner of the window
+    pub fn draw(&self, draw: &Draw, window: Rect) {
+        let lines = self.lines();
+        let hud = Rect::from_w_h(240., LINE_HEIGHT * lines.len() as f32 + 10.)
+            .top_left_of(window)
+            .shift(Vec2::new(10., -10.));
+
+        draw_lines(draw, hud, &lines, 1);
+    }
+
+    /// Draws the stats in a panel of PANEL_HEIGHT, the algorithm on top
+    /// and the figures in two columns below
+    pub fn draw_in(&self, draw: &Draw, panel: Rect) {
+        draw_lines(draw, panel, &self.lines(), 2);
+    }
+}
+
+/// Writes lines over a dark background, after the first one they are
+/// spread over the columns
+pub fn draw_lines(draw: &Draw, area: Rect, lines: &[String], columns: usize) {
+    draw.rect().xy(area.xy()).wh(area.wh()).color(*COLOR_HUD);
+
+    let width = (area.w() - 16.) / columns as f32;
+    for (n, line) in lines.iter().enumerate() {
+        let (x, w, row) = match n {
+            0 => (area.x(), area.w() - 16., 0),
+            _ => {
+                let column = (n - 1) % columns;
+                let x = area.left() + 8. + width * (column as f32 + 0.5);
+                (x, width, 1 + (n - 1) / columns)
+            }
+        };
+        let y = area.top() - 5. - LINE_HEIGHT * (row as f32 + 0.5);
+
+        draw.text(line)
+            .x_y(x, y)
+            .w(w)
+            .left_justify()
+            .font_size(13)
+            .color(WHITE);
+    }
+}
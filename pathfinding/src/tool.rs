@@ -0,0 +1,286 @@
+/// Editing tools, what a drag of the mouse paints on the board
+use crate::board::{CellType, Terrain};
+use nannou::prelude::*;
+
+const MAX_RADIUS: usize = 20;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Tool {
+    /// Cells around the mouse, as long as the button is held
+    Brush,
+    /// Straight line from where the drag started
+    Line,
+    Rectangle,
+    /// Border of a rectangle
+    Outline,
+    /// Region of the clicked cell's type
+    Fill,
+}
+
+impl Tool {
+    pub fn next(&self) -> Tool {
+        match self {
+            Tool::Brush => Tool::Line,
+            Tool::Line => Tool::Rectangle,
+            Tool::Rectangle => Tool::Outline,
+            Tool::Outline => Tool::Fill,
+            Tool::Fill => Tool::Brush,
+        }
+    }
+
+    /// Shapes are only painted once the button is released
+    fn is_shape(&self) -> bool {
+        matches!(self, Tool::Line | Tool::Rectangle | Tool::Outline)
+    }
+}
+
+/// A drag of the mouse over the board
+struct Stroke {
+    /// Cell the drag started on
+    anchor: (usize, usize),
+    /// Cell under the mouse at the previous frame
+    last: (usize, usize),
+    /// Only the cell clicked gets painted, whatever the tool
+    single: bool,
+}
+
+pub struct Editor {
+    pub tool: Tool,
+    /// Cells painted around the mouse by the brush, 0 for a single one
+    pub radius: usize,
+    stroke: Option<Stroke>,
+}
+
+impl Default for Editor {
+    fn default() -> Editor {
+        Editor {
+            tool: Tool::Brush,
+            radius: 0,
+            stroke: None,
+        }
+    }
+}
+
+impl Editor {
+    pub fn grow(&mut self) {
+        self.radius = (self.radius + 1).min(MAX_RADIUS);
+    }
+
+    pub fn shrink(&mut self) {
+        self.radius = self.radius.saturating_sub(1);
+    }
+
+    /// Cells to paint at this frame, knowing the cell under the mouse while
+    /// the button is held, which is lost outside the board. A single cell
+    /// is painted for types that can't cover several, like the start.
+    pub fn update(
+        &mut self,
+        terrain: &Terrain,
+        held: bool,
+        cell: Option<(usize, usize)>,
+        single: bool,
+    ) -> Vec<(usize, usize)> {
+        if !held {
+            // the shape goes on the board when the button is released
+            return match self.stroke.take() {
+                Some(stroke) if self.tool.is_shape() && !stroke.single => {
+                    self.shape(stroke.anchor, stroke.last)
+                }
+                _ => Vec::new(),
+            };
+        }
+        let Some(cell) = cell else {
+            return Vec::new();
+        };
+
+        match &mut self.stroke {
+            None => {
+                self.stroke = Some(Stroke {
+                    anchor: cell,
+                    last: cell,
+                    single,
+                });
+                match self.tool {
+                    _ if single => vec![cell],
+                    Tool::Brush => self.brush(terrain, cell, cell),
+                    Tool::Fill => terrain.region(cell.0, cell.1),
+                    _ => Vec::new(),
+                }
+            }
+            Some(stroke) => {
+                let last = std::mem::replace(&mut stroke.last, cell);
+                match self.tool {
+                    _ if stroke.single => Vec::new(),
+                    // the mouse may skip cells between two frames
+                    Tool::Brush if last != cell => {
+                        self.brush(terrain, last, cell)
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.stroke.is_some()
+    }
+
+    /// Forgets the current drag without painting it
+    pub fn cancel(&mut self) {
+        self.stroke = None;
+    }
+
+    /// Cells of the brush dragged from one cell to another
+    fn brush(
+        &self,
+        terrain: &Terrain,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Vec<(usize, usize)> {
+        let r = self.radius as isize;
+        let mut cells = Vec::new();
+
+        for (i, j) in line(from, to) {
+            for di in -r..=r {
+                for dj in -r..=r {
+                    // round brush
+                    if di * di + dj * dj > r * r + r {
+                        continue;
+                    }
+                    let (Some(k), Some(l)) =
+                        (i.checked_add_signed(di), j.checked_add_signed(dj))
+                    else {
+                        continue;
+                    };
+                    if k < terrain.width() && l < terrain.height() {
+                        cells.push((k, l));
+                    }
+                }
+            }
+        }
+
+        cells
+    }
+
+    fn shape(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Vec<(usize, usize)> {
+        match self.tool {
+            Tool::Line => line(from, to),
+            Tool::Rectangle => rectangle(from, to, true),
+            Tool::Outline => rectangle(from, to, false),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Draws the shape being dragged, in the colour of the cell type
+    pub fn draw(&self, draw: &Draw, terrain: &Terrain, ctype: CellType) {
+        let Some(stroke) = self
+            .stroke
+            .as_ref()
+            .filter(|stroke| self.tool.is_shape() && !stroke.single)
+        else {
+            return;
+        };
+        let (Some(a), Some(b)) = (
+            terrain.get(stroke.anchor.0, stroke.anchor.1),
+            terrain.get(stroke.last.0, stroke.last.1),
+        ) else {
+            return;
+        };
+        let size = terrain.cell_size();
+        let color = ctype.color();
+
+        match self.tool {
+            Tool::Line => {
+                draw.line().start(a.pos).end(b.pos).weight(size).color(rgba(
+                    color.red,
+                    color.green,
+                    color.blue,
+                    0xA0,
+                ));
+            }
+            _ => {
+                let area = Rect::from_corners(a.pos, b.pos).pad(-size / 2.);
+                let fill = match self.tool {
+                    Tool::Rectangle => 0xA0,
+                    _ => 0x00,
+                };
+
+                draw.rect()
+                    .xy(area.xy())
+                    .wh(area.wh())
+                    .color(rgba(color.red, color.green, color.blue, fill))
+                    .stroke(color)
+                    .stroke_weight(size / 4.);
+            }
+        }
+    }
+}
+
+/// Cells on a straight line between two cells, Bresenham's way
+pub fn line(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (x1, y1) = (to.0 as isize, to.1 as isize);
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+    let mut error = dx + dy;
+    let mut cells = Vec::new();
+
+    loop {
+        cells.push((x as usize, y as usize));
+        if (x, y) == (x1, y1) {
+            return cells;
+        }
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Cells of the rectangle with two opposite corners, or of its border only
+pub fn rectangle(
+    a: (usize, usize),
+    b: (usize, usize),
+    filled: bool,
+) -> Vec<(usize, usize)> {
+    let (left, right) = (a.0.min(b.0), a.0.max(b.0));
+    let (bottom, top) = (a.1.min(b.1), a.1.max(b.1));
+
+    (left..=right)
+        .flat_map(|i| (bottom..=top).map(move |j| (i, j)))
+        .filter(|&(i, j)| {
+            filled || i == left || i == right || j == bottom || j == top
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_strokes_paint_the_clicked_cell_only() {
+        let terrain = Terrain::with_size(10, 10, 1.);
+        let mut editor = Editor {
+            radius: 2,
+            ..Default::default()
+        };
+
+        for tool in [Tool::Brush, Tool::Rectangle, Tool::Fill] {
+            editor.tool = tool;
+            let mut cells = editor.update(&terrain, true, Some((2, 2)), true);
+            cells.extend(editor.update(&terrain, true, Some((6, 5)), true));
+            cells.extend(editor.update(&terrain, false, None, true));
+            assert_eq!(cells, vec![(2, 2)], "{:?}", tool);
+        }
+    }
+}
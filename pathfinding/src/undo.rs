@@ -0,0 +1,192 @@
+/// Undo and redo of the changes made to the map, a whole drag, generation
+/// or load being a single action
+use crate::board::{MapChanges, MapSnapshot, Terrain};
+
+/// Actions kept, the oldest ones are forgotten
+const MAX_ACTIONS: usize = 100;
+
+enum Action {
+    Edit(MapChanges),
+    /// The loaded map may not have the same size, both are kept whole
+    Load {
+        before: Box<Terrain>,
+        after: Box<Terrain>,
+    },
+}
+
+#[derive(Default)]
+pub struct UndoStack {
+    done: Vec<Action>,
+    undone: Vec<Action>,
+    /// Map before the action going on
+    pending: Option<MapSnapshot>,
+}
+
+impl UndoStack {
+    /// Keeps the map as it is before an action, unless one is already
+    /// going on
+    pub fn begin(&mut self, terrain: &Terrain) {
+        if self.pending.is_none() {
+            self.pending = Some(terrain.snapshot());
+        }
+    }
+
+    /// Ends the action going on, which is kept if it changed the map
+    pub fn end(&mut self, terrain: &Terrain) {
+        let Some(snapshot) = self.pending.take() else {
+            return;
+        };
+        if let Some(changes) = terrain.changes_since(&snapshot) {
+            self.push(Action::Edit(changes));
+        }
+    }
+
+    /// Keeps the terrain replaced by a loaded one
+    pub fn push_load(&mut self, before: Terrain, after: &Terrain) {
+        self.pending = None;
+        self.push(Action::Load {
+            before: Box::new(before),
+            after: Box::new(after.clone()),
+        });
+    }
+
+    fn push(&mut self, action: Action) {
+        self.done.push(action);
+        if self.done.len() > MAX_ACTIONS {
+            self.done.remove(0);
+        }
+        self.undone.clear();
+    }
+
+    /// Undoes the last action, returns false when there is none
+    pub fn undo(&mut self, terrain: &mut Terrain) -> bool {
+        self.end(terrain);
+        let Some(action) = self.done.pop() else {
+            return false;
+        };
+
+        match &action {
+            Action::Edit(changes) => terrain.undo_map_changes(changes),
+            Action::Load { before, .. } => replace(terrain, before),
+        }
+        self.undone.push(action);
+        true
+    }
+
+    /// Does the last undone action again, returns false when there is none
+    pub fn redo(&mut self, terrain: &mut Terrain) -> bool {
+        let Some(action) = self.undone.pop() else {
+            return false;
+        };
+
+        match &action {
+            Action::Edit(changes) => terrain.redo_map_changes(changes),
+            Action::Load { after, .. } => replace(terrain, after),
+        }
+        self.done.push(action);
+        true
+    }
+}
+
+/// Swaps in another map, the search settings stay as they are
+fn replace(terrain: &mut Terrain, other: &Terrain) {
+    let mut other = other.clone();
+    other.connectivity = terrain.connectivity;
+    other.corner_cutting = terrain.corner_cutting;
+    other.single_start = terrain.single_start;
+
+    *terrain = other;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::CellType;
+
+    fn types(terrain: &Terrain) -> Vec<CellType> {
+        (0..terrain.width())
+            .flat_map(|i| (0..terrain.height()).map(move |j| (i, j)))
+            .map(|(i, j)| terrain.get(i, j).unwrap().ctype)
+            .collect()
+    }
+
+    #[test]
+    fn a_drag_is_undone_and_redone_at_once() {
+        let mut terrain = Terrain::with_size(5, 4, 1.);
+        let mut undo = UndoStack::default();
+        terrain.set(0, 0, CellType::Start);
+        let before = types(&terrain);
+
+        undo.begin(&terrain);
+        terrain.set(1, 1, CellType::Wall);
+        undo.begin(&terrain);
+        terrain.set(2, 1, CellType::Wall);
+        // the new start erases the old one
+        terrain.set(4, 3, CellType::Start);
+        undo.end(&terrain);
+        let after = types(&terrain);
+
+        assert!(undo.undo(&mut terrain));
+        assert_eq!(types(&terrain), before);
+        assert_eq!(terrain.starts, vec![(0, 0)]);
+        assert!(!undo.undo(&mut terrain));
+
+        assert!(undo.redo(&mut terrain));
+        assert_eq!(types(&terrain), after);
+        assert_eq!(terrain.starts, vec![(4, 3)]);
+        assert!(!undo.redo(&mut terrain));
+    }
+
+    #[test]
+    fn actions_without_changes_are_forgotten() {
+        let mut terrain = Terrain::with_size(5, 4, 1.);
+        let mut undo = UndoStack::default();
+
+        undo.begin(&terrain);
+        terrain.set(1, 1, CellType::Wall);
+        terrain.set(1, 1, CellType::Empty);
+        undo.end(&terrain);
+
+        assert!(!undo.undo(&mut terrain));
+    }
+
+    #[test]
+    fn loads_keep_both_maps_and_the_settings() {
+        let mut terrain = Terrain::with_size(5, 4, 1.);
+        let mut undo = UndoStack::default();
+        terrain.set(2, 2, CellType::Grass);
+        terrain.single_start = false;
+
+        let mut loaded = Terrain::with_size(3, 7, 1.);
+        loaded.set(1, 6, CellType::Wall);
+        let before = std::mem::replace(&mut terrain, loaded);
+        undo.push_load(before, &terrain);
+
+        assert!(undo.undo(&mut terrain));
+        assert_eq!((terrain.width(), terrain.height()), (5, 4));
+        assert_eq!(terrain.get(2, 2).unwrap().ctype, CellType::Grass);
+        // settings are those of the board being used
+        assert!(terrain.single_start);
+
+        assert!(undo.redo(&mut terrain));
+        assert_eq!((terrain.width(), terrain.height()), (3, 7));
+        assert_eq!(terrain.get(1, 6).unwrap().ctype, CellType::Wall);
+    }
+
+    #[test]
+    fn a_new_action_drops_what_was_undone() {
+        let mut terrain = Terrain::with_size(5, 4, 1.);
+        let mut undo = UndoStack::default();
+
+        undo.begin(&terrain);
+        terrain.set(1, 1, CellType::Wall);
+        undo.end(&terrain);
+        undo.undo(&mut terrain);
+
+        undo.begin(&terrain);
+        terrain.set(3, 3, CellType::Sand);
+        undo.end(&terrain);
+
+        assert!(!undo.redo(&mut terrain));
+    }
+}